@@ -0,0 +1,109 @@
+use std::collections::{HashMap, VecDeque};
+
+use super::{Id, Register};
+
+/// A bounded cache of data derived from items, keyed by their ids.
+///
+/// When full, the least recently used entry is evicted.
+/// Recency is tracked with a queue, so each access costs time linear to the capacity;
+/// this is meant for small caches of values expensive to compute.
+pub struct IdCache<T: Register, V> {
+    cap: usize,
+    map: HashMap<Id<T>, V>,
+    order: VecDeque<Id<T>>,
+}
+
+impl<T: Register, V> IdCache<T, V> {
+    /// Create an empty cache holding at most `cap` entries.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `cap` is zero.
+    pub fn new(cap: usize) -> Self {
+        assert!(cap > 0, "zero capacity");
+        Self {
+            cap,
+            map: HashMap::with_capacity(cap),
+            order: VecDeque::with_capacity(cap),
+        }
+    }
+
+    /// Get the cached value for `id`, computing and caching it with `f` on miss.
+    pub fn get_or_compute(&mut self, id: Id<T>, f: impl FnOnce(&Id<T>) -> V) -> &V {
+        if self.map.contains_key(&id) {
+            self.touch(id);
+        } else {
+            if self.map.len() == self.cap {
+                let old = self.order.pop_front().unwrap();
+                self.map.remove(&old);
+            }
+            self.map.insert(id, f(&id));
+            self.order.push_back(id);
+        }
+        &self.map[&id]
+    }
+
+    /// Get the cached value for `id` without computing it.
+    pub fn get(&mut self, id: &Id<T>) -> Option<&V> {
+        if !self.map.contains_key(id) {
+            return None;
+        }
+        self.touch(*id);
+        self.map.get(id)
+    }
+
+    /// Whether a value for `id` is cached.
+    pub fn contains(&self, id: &Id<T>) -> bool {
+        self.map.contains_key(id)
+    }
+
+    /// Number of cached values.
+    pub fn len(&self) -> usize {
+        self.map.len()
+    }
+
+    /// Whether nothing is cached.
+    pub fn is_empty(&self) -> bool {
+        self.map.is_empty()
+    }
+
+    /// Drop all cached values.
+    pub fn clear(&mut self) {
+        self.map.clear();
+        self.order.clear();
+    }
+
+    fn touch(&mut self, id: Id<T>) {
+        if let Some(i) = self.order.iter().position(|x| *x == id) {
+            self.order.remove(i);
+        }
+        self.order.push_back(id);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn evicts_least_recently_used() {
+        let (a, b, c) = (Id::new("core:a"), Id::new("core:b"), Id::new("core:c"));
+        let mut cache: IdCache<(), usize> = IdCache::new(2);
+        cache.get_or_compute(a, |i| i.len());
+        cache.get_or_compute(b, |i| i.len());
+        assert_eq!(cache.get(&a), Some(&6));
+        cache.get_or_compute(c, |i| i.len());
+        assert_eq!(cache.len(), 2);
+        assert!(cache.contains(&a));
+        assert!(!cache.contains(&b));
+        assert!(cache.contains(&c));
+    }
+
+    #[test]
+    fn computes_only_on_miss() {
+        let a = Id::new("core:a");
+        let mut cache: IdCache<(), usize> = IdCache::new(1);
+        assert_eq!(*cache.get_or_compute(a, |_| 1), 1);
+        assert_eq!(*cache.get_or_compute(a, |_| 2), 1);
+    }
+}
@@ -0,0 +1,47 @@
+use std::{error::Error, fmt::Display};
+
+/// Error returned when inserting a new entry into a full `LimitedTab`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct LimitExceededError {
+    /// The maximum number of entries of the table.
+    pub limit: usize,
+}
+
+impl Display for LimitExceededError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "registry table limited to {} entries is full",
+            self.limit
+        )
+    }
+}
+
+impl Error for LimitExceededError {}
+
+/// Error returned by `RegTab::from_parts`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FromPartsError {
+    /// The numbers of ids and values differ.
+    LengthMismatch {
+        /// Number of ids given.
+        ids: usize,
+        /// Number of values given.
+        values: usize,
+    },
+    /// An id is given more than once.
+    DuplicateId(&'static str),
+}
+
+impl Display for FromPartsError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::LengthMismatch { ids, values } => {
+                write!(f, "got {ids} ids but {values} values")
+            }
+            Self::DuplicateId(id) => write!(f, "duplicate id {id}"),
+        }
+    }
+}
+
+impl Error for FromPartsError {}
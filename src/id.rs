@@ -1,5 +1,6 @@
 use std::{
     borrow::Borrow,
+    cmp::Ordering,
     fmt::{Debug, Display},
     hash::Hash,
     marker::PhantomData,
@@ -24,13 +25,26 @@ pub struct Id<T: Register> {
 
 impl<T: Register> Id<T> {
     /// Interpret a string as an `Id`.
+    ///
+    /// In debug builds, this panics if the module or identifier part is empty.
     pub const fn new(id: &'static str) -> Self {
+        debug_assert!(parts_non_empty(id), "empty part in id");
+        Self::from_key(id)
+    }
+
+    /// Wrap a key stored in a registry table, which may not satisfy the checks of `new`.
+    pub(crate) const fn from_key(id: &'static str) -> Self {
         Self {
             id,
             _phantom: PhantomData,
         }
     }
 
+    /// The full identifier string.
+    pub const fn as_str(&self) -> &'static str {
+        self.id
+    }
+
     /// The identifier part.
     pub fn idname(&self) -> &'static str {
         self.id.split(':').last().unwrap()
@@ -40,6 +54,171 @@ impl<T: Register> Id<T> {
     pub fn modname(&self) -> &'static str {
         self.id.split(':').next().unwrap()
     }
+
+    /// Reinterpret the id as one for another type.
+    ///
+    /// It is up to the caller to ensure the id is meaningful for `U`.
+    pub const fn cast<U: Register>(self) -> Id<U> {
+        Id::from_key(self.id)
+    }
+
+    /// Whether this and an id of another type have the same string.
+    pub fn same_path<U: Register>(&self, other: &Id<U>) -> bool {
+        self.id == other.id
+    }
+
+    /// Compare ids treating runs of digits as numbers, so that `tier2` sorts before `tier10`.
+    ///
+    /// This is meant for presentation; `Ord` remains a plain lexical comparison.
+    pub fn cmp_natural(&self, other: &Self) -> Ordering {
+        natural_cmp(self.id, other.id)
+    }
+
+    /// Compare ids with modules listed in `priority` first, in the order given.
+    ///
+    /// Ids in unlisted modules come after, and ties are broken by plain lexical order.
+    pub fn cmp_with_priority(&self, other: &Self, priority: &[&str]) -> Ordering {
+        let rank = |m| priority.iter().position(|p| *p == m).unwrap_or(usize::MAX);
+        rank(self.modname())
+            .cmp(&rank(other.modname()))
+            .then_with(|| self.id.cmp(other.id))
+    }
+
+    /// Display only the identifier part if the id belongs to `module`, or the whole id otherwise.
+    pub fn display_relative(&self, module: &str) -> impl Display {
+        self.display_as(if self.modname() == module {
+            IdFormat::NameOnly
+        } else {
+            IdFormat::Colon
+        })
+    }
+
+    /// Display the module part with the identifier part replaced by its hash in hex,
+    /// as `module:0123456789abcdef`.
+    ///
+    /// Everything after the first `:` counts as the identifier part.
+    /// An id without `:` is hashed whole and displayed as the hash alone.
+    /// The hash is 64-bit FNV-1a, which is stable across builds but not cryptographic.
+    pub fn display_redacted(&self) -> impl Display {
+        let (modname, name) = match self.id.split_once(':') {
+            Some((m, n)) => (Some(m), n),
+            None => (None, self.id),
+        };
+        let hash = name.bytes().fold(0xcbf29ce484222325u64, |h, b| {
+            (h ^ b as u64).wrapping_mul(0x100000001b3)
+        });
+        IdRedacted { modname, hash }
+    }
+
+    /// Display the id in specified format.
+    pub fn display_as(&self, fmt: IdFormat) -> impl Display {
+        IdDisplay {
+            id: self.id,
+            modname: self.modname(),
+            idname: self.idname(),
+            fmt,
+        }
+    }
+}
+
+const fn parts_non_empty(id: &str) -> bool {
+    let b = id.as_bytes();
+    !b.is_empty() && b[0] != b':' && b[b.len() - 1] != b':'
+}
+
+/// Check an id like `Id::new` does, but in all builds, for `regtab!`.
+#[doc(hidden)]
+pub const fn checked_key(id: &'static str) -> &'static str {
+    assert!(parts_non_empty(id), "empty part in id");
+    id
+}
+
+fn natural_cmp(a: &str, b: &str) -> Ordering {
+    let (a, b) = (a.as_bytes(), b.as_bytes());
+    let (mut i, mut j) = (0, 0);
+    while i < a.len() && j < b.len() {
+        if a[i].is_ascii_digit() && b[j].is_ascii_digit() {
+            let (si, sj) = (i, j);
+            while i < a.len() && a[i].is_ascii_digit() {
+                i += 1;
+            }
+            while j < b.len() && b[j].is_ascii_digit() {
+                j += 1;
+            }
+            let x = trim_zeros(&a[si..i]);
+            let y = trim_zeros(&b[sj..j]);
+            let ord = x
+                .len()
+                .cmp(&y.len())
+                .then_with(|| x.cmp(y))
+                .then_with(|| (i - si).cmp(&(j - sj)));
+            if ord.is_ne() {
+                return ord;
+            }
+        } else {
+            let ord = a[i].cmp(&b[j]);
+            if ord.is_ne() {
+                return ord;
+            }
+            i += 1;
+            j += 1;
+        }
+    }
+    (a.len() - i).cmp(&(b.len() - j))
+}
+
+fn trim_zeros(digits: &[u8]) -> &[u8] {
+    let n = digits.iter().take_while(|d| **d == b'0').count();
+    &digits[n..]
+}
+
+struct IdRedacted {
+    modname: Option<&'static str>,
+    hash: u64,
+}
+
+impl Display for IdRedacted {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.modname {
+            Some(m) => write!(f, "{}:{:016x}", m, self.hash),
+            None => write!(f, "{:016x}", self.hash),
+        }
+    }
+}
+
+/// Formats to display an `Id` in, see `Id::display_as`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum IdFormat {
+    /// `module:name`, the same as `Display`.
+    Colon,
+    /// `module/name`.
+    Slash,
+    /// `name`.
+    NameOnly,
+    /// `module`.
+    ModuleOnly,
+    /// `[module] name`.
+    Bracketed,
+}
+
+struct IdDisplay {
+    id: &'static str,
+    modname: &'static str,
+    idname: &'static str,
+    fmt: IdFormat,
+}
+
+impl Display for IdDisplay {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let (m, n) = (self.modname, self.idname);
+        match self.fmt {
+            IdFormat::Colon => f.write_str(self.id),
+            IdFormat::Slash => write!(f, "{m}/{n}"),
+            IdFormat::NameOnly => f.write_str(n),
+            IdFormat::ModuleOnly => f.write_str(m),
+            IdFormat::Bracketed => write!(f, "[{m}] {n}"),
+        }
+    }
 }
 
 impl<T: Register> Clone for Id<T> {
@@ -102,13 +281,75 @@ impl<T: Register> Hash for Id<T> {
     }
 }
 
+/// A placeholder `default:default`, only to enable `#[derive(Default)]` on types holding an `Id`.
+///
+/// This carries no meaning and is usually not registered.
+#[cfg(feature = "default-id")]
+impl<T: Register> Default for Id<T> {
+    fn default() -> Self {
+        Self::new("default:default")
+    }
+}
+
 impl<T: Register> From<&'static str> for Id<T> {
     fn from(value: &'static str) -> Self {
         Self::new(value)
     }
 }
 
-struct IdVisitor<T>(PhantomData<T>);
+/// An `Id` compared and hashed ignoring ASCII case, for use as keys in standard collections.
+///
+/// The wrapped id keeps its original casing.
+/// Keys of a map should be either all `CaseFoldId` or all `Id`; mixing them is not supported.
+pub struct CaseFoldId<T: Register>(pub Id<T>);
+
+impl<T: Register> Clone for CaseFoldId<T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T: Register> Copy for CaseFoldId<T> {}
+
+impl<T: Register> PartialEq for CaseFoldId<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.0.id.eq_ignore_ascii_case(other.0.id)
+    }
+}
+
+impl<T: Register> Eq for CaseFoldId<T> {}
+
+impl<T: Register> Hash for CaseFoldId<T> {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        for b in self.0.id.bytes() {
+            state.write_u8(b.to_ascii_lowercase());
+        }
+        state.write_u8(0xff);
+    }
+}
+
+impl<T: Register> Display for CaseFoldId<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        Display::fmt(&self.0, f)
+    }
+}
+
+impl<T: Register> Debug for CaseFoldId<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        Debug::fmt(&self.0, f)
+    }
+}
+
+impl<T: Register> From<Id<T>> for CaseFoldId<T> {
+    fn from(value: Id<T>) -> Self {
+        Self(value)
+    }
+}
+
+struct IdVisitor<T> {
+    trim: bool,
+    _phantom: PhantomData<T>,
+}
 
 impl<'de, T: HasRegTab> Visitor<'de> for IdVisitor<T> {
     type Value = Id<T>;
@@ -122,9 +363,10 @@ impl<'de, T: HasRegTab> Visitor<'de> for IdVisitor<T> {
         E: serde::de::Error,
     {
         let tab = T::reg_tab();
-        let res = tab.view(v, |k, _| *k);
+        let s = if self.trim { v.trim_ascii() } else { v };
+        let res = tab.view(s, |k, _| *k);
         match res {
-            Some(i) => Ok(Id::new(i)),
+            Some(i) => Ok(Id::from_key(i)),
             None => Err(E::invalid_value(
                 Unexpected::Str(v),
                 &"an already registered id string",
@@ -138,6 +380,186 @@ impl<'de, T: HasRegTab> Deserialize<'de> for Id<T> {
     where
         D: serde::Deserializer<'de>,
     {
-        deserializer.deserialize_str(IdVisitor::<T>(PhantomData))
+        deserializer.deserialize_str(IdVisitor::<T> {
+            trim: false,
+            _phantom: PhantomData,
+        })
+    }
+}
+
+/// Deserialize an `Id`, ignoring leading and trailing ASCII whitespace.
+///
+/// Use with `#[serde(deserialize_with = "fe3o4::id::deserialize_trimmed")]` for lenient configs.
+/// Only surrounding whitespace is removed; the rest must match a registered id exactly.
+pub fn deserialize_trimmed<'de, D, T>(deserializer: D) -> Result<Id<T>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+    T: HasRegTab,
+{
+    deserializer.deserialize_str(IdVisitor::<T> {
+        trim: true,
+        _phantom: PhantomData,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cmp(a: &'static str, b: &'static str) -> Ordering {
+        Id::<()>::new(a).cmp_natural(&Id::new(b))
+    }
+
+    #[test]
+    fn natural_order() {
+        assert_eq!(cmp("core:tier2", "core:tier10"), Ordering::Less);
+        assert_eq!(cmp("core:tier1", "core:tier2"), Ordering::Less);
+        assert_eq!(cmp("core:v1.2", "core:v1.10"), Ordering::Less);
+        assert_eq!(cmp("core:v1.10", "core:v2.1"), Ordering::Less);
+    }
+
+    #[test]
+    fn natural_leading_zeros() {
+        assert_eq!(cmp("core:tier2", "core:tier02"), Ordering::Less);
+        assert_eq!(cmp("core:tier02", "core:tier3"), Ordering::Less);
+        assert_eq!(cmp("core:tier002", "core:tier02"), Ordering::Greater);
+    }
+
+    #[test]
+    fn natural_trailing_digits() {
+        assert_eq!(cmp("core:tier", "core:tier1"), Ordering::Less);
+        assert_eq!(cmp("core:tier9", "core:tier10"), Ordering::Less);
+        assert_eq!(cmp("core:a1b", "core:a1"), Ordering::Greater);
+    }
+
+    #[test]
+    fn natural_digit_against_non_digit() {
+        assert_eq!(cmp("core:a1", "core:ab"), Ordering::Less);
+        assert_eq!(cmp("core:a_", "core:a1"), Ordering::Greater);
+        assert_eq!(cmp("core:1", "core:a"), Ordering::Less);
+    }
+
+    #[test]
+    fn natural_equal_only_when_identical() {
+        let ids = [
+            "core:tier",
+            "core:tier0",
+            "core:tier00",
+            "core:tier1",
+            "core:tier01",
+            "core:tier10",
+            "core:v1.2",
+            "core:v1.02",
+            "core:v01.2",
+            "core:a1b",
+            "core:a01b",
+            "core:ab",
+        ];
+        for a in ids {
+            for b in ids {
+                assert_eq!(cmp(a, b) == Ordering::Equal, a == b, "{a} vs {b}");
+                assert_eq!(cmp(a, b), cmp(b, a).reverse(), "{a} vs {b}");
+            }
+        }
+    }
+
+    fn fold(id: &'static str) -> CaseFoldId<()> {
+        CaseFoldId(Id::new(id))
+    }
+
+    #[test]
+    fn case_fold_eq_and_hash() {
+        use std::hash::{BuildHasher, RandomState};
+        let (a, b) = (fold("Core:Iron_Ore"), fold("core:IRON_ORE"));
+        assert_eq!(a, b);
+        assert_ne!(a, fold("core:iron_ore2"));
+        let s = RandomState::new();
+        assert_eq!(s.hash_one(a), s.hash_one(b));
+    }
+
+    #[test]
+    fn case_fold_set_lookup() {
+        use std::collections::HashSet;
+        let set: HashSet<_> = [fold("core:Iron_Ore"), fold("core:gold")].into();
+        assert!(set.contains(&fold("CORE:iron_ore")));
+        assert!(set.contains(&fold("core:GOLD")));
+        assert!(!set.contains(&fold("core:copper")));
+    }
+
+    #[test]
+    fn redacted_hides_name() {
+        let shown = Id::<()>::new("core:secret_boss")
+            .display_redacted()
+            .to_string();
+        let (m, h) = shown.split_once(':').unwrap();
+        assert_eq!(m, "core");
+        assert_eq!(h.len(), 16);
+        assert!(!shown.contains("secret"));
+        assert_eq!(
+            shown,
+            Id::<()>::new("core:secret_boss")
+                .display_redacted()
+                .to_string()
+        );
+        assert_ne!(
+            shown,
+            Id::<()>::new("core:secret_bass")
+                .display_redacted()
+                .to_string()
+        );
+    }
+
+    #[test]
+    fn redacted_without_separator() {
+        let shown = Id::<()>::new("secret_boss").display_redacted().to_string();
+        assert_eq!(shown.len(), 16);
+        assert!(!shown.contains("secret") && !shown.contains(':'));
+        let nested = Id::<()>::new("a:b:c").display_redacted().to_string();
+        assert!(nested.starts_with("a:") && nested.len() == 18);
+        assert_ne!(nested, Id::<()>::new("a:b").display_redacted().to_string());
+        assert_ne!(nested, Id::<()>::new("a:c").display_redacted().to_string());
+    }
+
+    struct Block;
+    struct Item;
+
+    #[test]
+    fn same_path_across_types() {
+        let block = Id::<Block>::new("core:stone");
+        assert!(block.same_path(&Id::<Item>::new("core:stone")));
+        assert!(!block.same_path(&Id::<Item>::new("core:dirt")));
+        assert!(!block.same_path(&Id::<Item>::new("deco:stone")));
+    }
+
+    #[test]
+    fn display_relative_modules() {
+        let id = Id::<()>::new("core:stone");
+        assert_eq!(id.display_relative("core").to_string(), "stone");
+        assert_eq!(id.display_relative("deco").to_string(), "core:stone");
+        assert_eq!(id.display_relative("cor").to_string(), "core:stone");
+    }
+
+    #[test]
+    fn cast_round_trip() {
+        let block = Id::<Block>::new("core:stone");
+        let item: Id<Item> = block.cast();
+        assert_eq!(item.as_str(), "core:stone");
+        let back: Id<Block> = item.cast();
+        assert_eq!(back.as_str(), block.as_str());
+        assert!(back.same_path(&block));
+    }
+
+    #[test]
+    fn priority_reorders_modules() {
+        let mut ids = [
+            "base:b", "core:b", "deco:a", "base:a", "mod:x", "core:a", "aux:z",
+        ]
+        .map(Id::<()>::new);
+        ids.sort_by(|a, b| a.cmp_with_priority(b, &["deco", "core"]));
+        let ids = ids.map(|i| i.as_str());
+        assert_eq!(
+            ids,
+            ["deco:a", "core:a", "core:b", "aux:z", "base:a", "base:b", "mod:x"]
+        );
     }
 }
@@ -1,13 +1,22 @@
+#[cfg(feature = "cache")]
+pub mod cache;
+pub mod err;
 pub mod id;
+pub mod limit;
+pub mod metrics;
+pub mod order;
 pub mod prelude;
+pub mod rp_compact;
+pub mod set;
+pub mod source;
 pub mod tab;
 
-use std::ops::Deref;
+use std::{cmp::Ordering, marker::PhantomData, ops::Deref};
 
 use dashmap::mapref::one::Ref;
 pub use prelude::*;
 use serde::{
-    de::{DeserializeOwned, Error, Unexpected},
+    de::{DeserializeOwned, Error, SeqAccess, Unexpected, Visitor},
     Deserialize, Serialize,
 };
 
@@ -18,6 +27,14 @@ impl<T> Register for T where T: 'static + Sized {}
 /// A smart pointer to an either registered resource or orphan.
 /// 
 /// This either refers to an item in registry table or holds an owned `T` instance.
+///
+/// # Thread Safety
+///
+/// `Send` and `Sync` are derived automatically from the variants:
+/// `Rp<T>` is `Send` when `T: Send + Sync` and `Sync` when `T: Sync`.
+/// A registered pointer may be moved to or shared with other threads,
+/// but note that it holds a read lock on a shard of the registry table while alive,
+/// so inserting into or removing from that shard blocks until it is dropped.
 pub enum Rp<T: Register> {
     /// The item is found in a registry table.
     Registered(Ref<'static, &'static str, T>),
@@ -25,6 +42,29 @@ pub enum Rp<T: Register> {
     Orphan(Box<T>),
 }
 
+impl<T: Register> Rp<T> {
+    /// Point to the registered item with specified id, if any.
+    pub fn registered(id: Id<T>) -> Option<Self>
+    where
+        T: HasRegTab,
+    {
+        Self::lookup(id.as_str())
+    }
+
+    /// Point to the registered item with id given as string, if any.
+    pub fn lookup(id: &str) -> Option<Self>
+    where
+        T: HasRegTab,
+    {
+        T::reg_tab().get(id).map(Self::Registered)
+    }
+
+    /// Hold an orphan item.
+    pub fn orphan(value: T) -> Self {
+        Self::Orphan(Box::new(value))
+    }
+}
+
 impl<T: HasRegTab> Deref for Rp<T> {
     type Target = T;
 
@@ -36,6 +76,91 @@ impl<T: HasRegTab> Deref for Rp<T> {
     }
 }
 
+/// Registered pointers are equal when they have the same id, and orphans when their values are.
+/// A registered pointer never equals an orphan.
+impl<T: Register> PartialEq for Rp<T>
+where
+    T: PartialEq,
+{
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Self::Registered(a), Self::Registered(b)) => a.key() == b.key(),
+            (Self::Orphan(a), Self::Orphan(b)) => a == b,
+            _ => false,
+        }
+    }
+}
+
+impl<T: Register> Eq for Rp<T> where T: Eq {}
+
+impl<T: Register> PartialOrd for Rp<T>
+where
+    T: Ord,
+{
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// Registered pointers are ordered by id and all come before orphans,
+/// which are ordered by value.
+impl<T: Register> Ord for Rp<T>
+where
+    T: Ord,
+{
+    fn cmp(&self, other: &Self) -> Ordering {
+        match (self, other) {
+            (Self::Registered(a), Self::Registered(b)) => a.key().cmp(b.key()),
+            (Self::Registered(_), Self::Orphan(_)) => Ordering::Less,
+            (Self::Orphan(_), Self::Registered(_)) => Ordering::Greater,
+            (Self::Orphan(a), Self::Orphan(b)) => a.cmp(b),
+        }
+    }
+}
+
+/// A smart pointer to an either registered resource or orphan, borrowing a registry table.
+///
+/// This is the non-`'static` counterpart of `Rp`, for registry tables that are not global,
+/// and cannot outlive the table it borrows.
+pub enum RpRef<'a, T: Register> {
+    /// The item is found in a registry table.
+    Registered(Ref<'a, &'static str, T>),
+    /// The item is orphan, or not in registry table.
+    Orphan(Box<T>),
+}
+
+impl<'a, T: Register> RpRef<'a, T> {
+    /// Point to the item with specified id in `tab`, if any.
+    pub fn registered(tab: &'a RegTab<T>, id: Id<T>) -> Option<Self> {
+        tab.get(id.as_str()).map(Self::Registered)
+    }
+
+    /// Hold an orphan item.
+    pub fn orphan(value: T) -> Self {
+        Self::Orphan(Box::new(value))
+    }
+}
+
+impl<T: Register> Deref for RpRef<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        match self {
+            RpRef::Registered(p) => p,
+            RpRef::Orphan(p) => p,
+        }
+    }
+}
+
+impl<T: Register> From<Rp<T>> for RpRef<'static, T> {
+    fn from(value: Rp<T>) -> Self {
+        match value {
+            Rp::Registered(r) => Self::Registered(r),
+            Rp::Orphan(v) => Self::Orphan(v),
+        }
+    }
+}
+
 impl<T: Register> Clone for Rp<T>
 where
     T: Clone + HasRegTab,
@@ -56,6 +181,9 @@ enum SerdeRp<T: HasRegTab> {
     Orphan(Box<T>),
 }
 
+/// Human-readable formats get the externally tagged `{"r": id}` or `{"o": value}`,
+/// while other formats get a tighter `(tag, payload)` tuple, tagged 0 for an id and 1 for an orphan.
+/// Data only round-trips within formats agreeing on `is_human_readable`.
 impl<T> Serialize for Rp<T>
 where
     T: Clone + Serialize + HasRegTab,
@@ -64,14 +192,53 @@ where
     where
         S: serde::Serializer,
     {
+        if !serializer.is_human_readable() {
+            return match self {
+                Rp::Registered(r) => (0u8, *r.key()).serialize(serializer),
+                Rp::Orphan(v) => (1u8, v).serialize(serializer),
+            };
+        }
         let serde = match self {
-            Rp::Registered(id) => SerdeRp::Registered((*id.key()).into()),
+            Rp::Registered(id) => SerdeRp::Registered(Id::from_key(id.key())),
             Rp::Orphan(v) => SerdeRp::Orphan(v.clone()),
         };
         serde.serialize(serializer)
     }
 }
 
+struct CompactRpVisitor<T>(PhantomData<T>);
+
+impl<'de, T> Visitor<'de> for CompactRpVisitor<T>
+where
+    T: DeserializeOwned + HasRegTab,
+{
+    type Value = SerdeRp<T>;
+
+    fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(formatter, "a tag followed by a registered id or an orphan")
+    }
+
+    fn visit_seq<A>(self, mut seq: A) -> Result<Self::Value, A::Error>
+    where
+        A: SeqAccess<'de>,
+    {
+        let tag: u8 = seq
+            .next_element()?
+            .ok_or_else(|| A::Error::invalid_length(0, &self))?;
+        let rp = match tag {
+            0 => seq.next_element()?.map(SerdeRp::Registered),
+            1 => seq.next_element()?.map(SerdeRp::Orphan),
+            _ => {
+                return Err(A::Error::invalid_value(
+                    Unexpected::Unsigned(tag as u64),
+                    &"tag 0 or 1",
+                ))
+            }
+        };
+        rp.ok_or_else(|| A::Error::invalid_length(1, &self))
+    }
+}
+
 impl<'de, T> Deserialize<'de> for Rp<T>
 where
     T: DeserializeOwned + HasRegTab,
@@ -80,20 +247,265 @@ where
     where
         D: serde::Deserializer<'de>,
     {
-        let d = SerdeRp::deserialize(deserializer)?;
+        let d = if deserializer.is_human_readable() {
+            SerdeRp::deserialize(deserializer)?
+        } else {
+            deserializer.deserialize_tuple(2, CompactRpVisitor(PhantomData))?
+        };
         match d {
-            SerdeRp::Registered(id) => {
-                let tab = T::reg_tab();
-                let got = tab.get(&*id);
-                if let Some(got) = got {
-                    return Ok(Rp::Registered(got));
-                }
-                Err(D::Error::invalid_value(
-                    Unexpected::StructVariant,
-                    &"an orphan or registered id",
-                ))
-            }
+            SerdeRp::Registered(id) => Rp::registered(id).ok_or_else(|| {
+                D::Error::invalid_value(Unexpected::StructVariant, &"an orphan or registered id")
+            }),
+            SerdeRp::Orphan(_) if !T::ALLOW_ORPHANS => Err(D::Error::invalid_value(
+                Unexpected::StructVariant,
+                &"a registered id, as orphans are not allowed",
+            )),
             SerdeRp::Orphan(v) => Ok(Rp::Orphan(v)),
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Serialize, Deserialize)]
+    struct Stone(u32);
+
+    crate::def_regtab!(Stone, STONES);
+
+    #[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+    struct Core(u32);
+
+    crate::def_regtab!(Core, CORES, allow_orphans = false);
+
+    #[test]
+    fn rp_registered() {
+        Stone::reg_tab().insert("core:stone", Stone(1));
+        let rp = Rp::registered(Id::<Stone>::new("core:stone")).unwrap();
+        assert!(matches!(rp, Rp::Registered(_)));
+        assert_eq!(*rp, Stone(1));
+        drop(rp);
+        assert!(Rp::registered(Id::<Stone>::new("core:missing")).is_none());
+    }
+
+    #[test]
+    fn rp_lookup() {
+        Stone::reg_tab().insert("core:granite", Stone(2));
+        assert_eq!(*Rp::<Stone>::lookup("core:granite").unwrap(), Stone(2));
+        assert!(Rp::<Stone>::lookup("core:basalt").is_none());
+        assert!(Rp::<Stone>::lookup("granite").is_none());
+        assert!(Rp::<Stone>::lookup("").is_none());
+    }
+
+    #[test]
+    fn rp_orphan() {
+        let rp = Rp::orphan(Stone(3));
+        assert!(matches!(rp, Rp::Orphan(_)));
+        assert_eq!(*rp, Stone(3));
+    }
+
+    #[test]
+    fn rp_deserialize_orphans() {
+        Core::reg_tab().insert("core:core", Core(1));
+        let rp: Rp<Stone> = serde_json::from_str(r#"{"o":4}"#).unwrap();
+        assert_eq!(*rp, Stone(4));
+        let rp: Rp<Core> = serde_json::from_str(r#"{"r":"core:core"}"#).unwrap();
+        assert_eq!(*rp, Core(1));
+        drop(rp);
+        assert!(serde_json::from_str::<Rp<Core>>(r#"{"o":4}"#).is_err());
+    }
+
+    #[test]
+    fn rp_mixed_order() {
+        Stone::reg_tab().insert("core:slate", Stone(9));
+        Stone::reg_tab().insert("core:marble", Stone(8));
+        let mut rps = [
+            Rp::orphan(Stone(2)),
+            Rp::<Stone>::lookup("core:slate").unwrap(),
+            Rp::orphan(Stone(1)),
+            Rp::lookup("core:marble").unwrap(),
+        ];
+        rps.sort();
+        assert!(matches!(&rps[0], Rp::Registered(r) if *r.key() == "core:marble"));
+        assert!(matches!(&rps[1], Rp::Registered(r) if *r.key() == "core:slate"));
+        assert_eq!(*rps[2], Stone(1));
+        assert_eq!(*rps[3], Stone(2));
+        // The registered value is larger than any orphan, yet it still sorts first.
+        assert!(rps[0] < Rp::orphan(Stone(0)));
+    }
+
+    #[test]
+    fn rp_eq_agrees_with_ord() {
+        Stone::reg_tab().insert("core:chalk", Stone(7));
+        Stone::reg_tab().insert("core:flint", Stone(7));
+        let rps = [
+            Rp::<Stone>::lookup("core:chalk").unwrap(),
+            Rp::lookup("core:chalk").unwrap(),
+            Rp::lookup("core:flint").unwrap(),
+            Rp::orphan(Stone(7)),
+            Rp::orphan(Stone(7)),
+            Rp::orphan(Stone(6)),
+        ];
+        for a in &rps {
+            for b in &rps {
+                assert_eq!(a == b, a.cmp(b) == Ordering::Equal);
+                assert_eq!(a.cmp(b), b.cmp(a).reverse());
+            }
+        }
+        assert!(rps[0] == rps[1] && rps[0] != rps[2] && rps[3] == rps[4]);
+    }
+
+    /// Serializer reporting itself as not human-readable, to exercise the compact `Rp` form.
+    struct Compact<S>(S);
+
+    macro_rules! forward {
+        ($($f:ident($($a:ident: $t:ty),*) -> $r:ty;)*) => {
+            $(fn $f(self, $($a: $t),*) -> Result<$r, Self::Error> {
+                self.0.$f($($a),*)
+            })*
+        };
+    }
+
+    impl<S: serde::Serializer> serde::Serializer for Compact<S> {
+        type Ok = S::Ok;
+        type Error = S::Error;
+        type SerializeSeq = S::SerializeSeq;
+        type SerializeTuple = S::SerializeTuple;
+        type SerializeTupleStruct = S::SerializeTupleStruct;
+        type SerializeTupleVariant = S::SerializeTupleVariant;
+        type SerializeMap = S::SerializeMap;
+        type SerializeStruct = S::SerializeStruct;
+        type SerializeStructVariant = S::SerializeStructVariant;
+
+        fn is_human_readable(&self) -> bool {
+            false
+        }
+
+        forward! {
+            serialize_bool(v: bool) -> S::Ok;
+            serialize_i8(v: i8) -> S::Ok;
+            serialize_i16(v: i16) -> S::Ok;
+            serialize_i32(v: i32) -> S::Ok;
+            serialize_i64(v: i64) -> S::Ok;
+            serialize_u8(v: u8) -> S::Ok;
+            serialize_u16(v: u16) -> S::Ok;
+            serialize_u32(v: u32) -> S::Ok;
+            serialize_u64(v: u64) -> S::Ok;
+            serialize_f32(v: f32) -> S::Ok;
+            serialize_f64(v: f64) -> S::Ok;
+            serialize_char(v: char) -> S::Ok;
+            serialize_str(v: &str) -> S::Ok;
+            serialize_bytes(v: &[u8]) -> S::Ok;
+            serialize_none() -> S::Ok;
+            serialize_unit() -> S::Ok;
+            serialize_unit_struct(name: &'static str) -> S::Ok;
+            serialize_unit_variant(name: &'static str, i: u32, var: &'static str) -> S::Ok;
+            serialize_seq(len: Option<usize>) -> S::SerializeSeq;
+            serialize_tuple(len: usize) -> S::SerializeTuple;
+            serialize_tuple_struct(name: &'static str, len: usize) -> S::SerializeTupleStruct;
+            serialize_tuple_variant(
+                name: &'static str,
+                i: u32,
+                var: &'static str,
+                len: usize
+            ) -> S::SerializeTupleVariant;
+            serialize_map(len: Option<usize>) -> S::SerializeMap;
+            serialize_struct(name: &'static str, len: usize) -> S::SerializeStruct;
+            serialize_struct_variant(
+                name: &'static str,
+                i: u32,
+                var: &'static str,
+                len: usize
+            ) -> S::SerializeStructVariant;
+        }
+
+        fn serialize_some<V: ?Sized + Serialize>(self, v: &V) -> Result<S::Ok, S::Error> {
+            self.0.serialize_some(v)
+        }
+
+        fn serialize_newtype_struct<V: ?Sized + Serialize>(
+            self,
+            name: &'static str,
+            v: &V,
+        ) -> Result<S::Ok, S::Error> {
+            self.0.serialize_newtype_struct(name, v)
+        }
+
+        fn serialize_newtype_variant<V: ?Sized + Serialize>(
+            self,
+            name: &'static str,
+            i: u32,
+            var: &'static str,
+            v: &V,
+        ) -> Result<S::Ok, S::Error> {
+            self.0.serialize_newtype_variant(name, i, var, v)
+        }
+    }
+
+    /// Deserializer reporting itself as not human-readable, to exercise the compact `Rp` form.
+    struct CompactDe<D>(D);
+
+    impl<'de, D: serde::Deserializer<'de>> serde::Deserializer<'de> for CompactDe<D> {
+        type Error = D::Error;
+
+        fn is_human_readable(&self) -> bool {
+            false
+        }
+
+        fn deserialize_any<V: Visitor<'de>>(self, v: V) -> Result<V::Value, D::Error> {
+            self.0.deserialize_any(v)
+        }
+
+        fn deserialize_tuple<V: Visitor<'de>>(
+            self,
+            len: usize,
+            v: V,
+        ) -> Result<V::Value, D::Error> {
+            self.0.deserialize_tuple(len, v)
+        }
+
+        serde::forward_to_deserialize_any! {
+            bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string bytes byte_buf
+            option unit unit_struct newtype_struct seq tuple_struct map struct enum identifier
+            ignored_any
+        }
+    }
+
+    fn to_compact<T: Clone + Serialize + HasRegTab>(rp: &Rp<T>) -> String {
+        let mut buf = vec![];
+        rp.serialize(Compact(&mut serde_json::Serializer::new(&mut buf)))
+            .unwrap();
+        String::from_utf8(buf).unwrap()
+    }
+
+    fn from_compact<T: DeserializeOwned + HasRegTab>(s: &str) -> Result<Rp<T>, serde_json::Error> {
+        Rp::deserialize(CompactDe(&mut serde_json::Deserializer::from_str(s)))
+    }
+
+    #[test]
+    fn rp_serde_not_human_readable() {
+        Stone::reg_tab().insert("core:shale", Stone(5));
+        let rp = Rp::<Stone>::lookup("core:shale").unwrap();
+        assert_eq!(serde_json::to_string(&rp).unwrap(), r#"{"r":"core:shale"}"#);
+        let compact = to_compact(&rp);
+        assert_eq!(compact, r#"[0,"core:shale"]"#);
+        drop(rp);
+        let rp = from_compact::<Stone>(&compact).unwrap();
+        assert!(matches!(&rp, Rp::Registered(r) if **r == Stone(5)));
+        drop(rp);
+        let orphan = Rp::orphan(Stone(6));
+        assert_eq!(serde_json::to_string(&orphan).unwrap(), r#"{"o":6}"#);
+        let compact = to_compact(&orphan);
+        assert_eq!(compact, "[1,6]");
+        assert!(from_compact::<Stone>(&compact).unwrap() == orphan);
+    }
+
+    #[test]
+    fn rp_serde_not_human_readable_invalid() {
+        assert!(from_compact::<Stone>(r#"[2,"core:shale"]"#).is_err());
+        assert!(from_compact::<Stone>(r#"[0,"core:missing"]"#).is_err());
+        assert!(from_compact::<Stone>("[1]").is_err());
+        assert!(from_compact::<Core>("[1,4]").is_err());
+    }
+}
@@ -0,0 +1,131 @@
+use std::{
+    ops::Deref,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+use dashmap::mapref::entry::Entry;
+
+use super::{err::LimitExceededError, Id, ReadTab, RegTab, Register};
+
+/// A registry table refusing new entries beyond a maximum count.
+///
+/// This bounds the size of tables filled from untrusted data.
+/// Overwriting an existing entry is always allowed, since it does not grow the table.
+/// A plain `RegTab` is unlimited.
+pub struct LimitedTab<T: Register> {
+    tab: ReadTab<T>,
+    len: AtomicUsize,
+    limit: usize,
+}
+
+impl<T: Register> LimitedTab<T> {
+    /// Create an empty table holding at most `limit` entries.
+    pub fn with_limit(limit: usize) -> Self {
+        Self::from_tab(RegTab::new(), limit)
+    }
+
+    /// Wrap an existing table, counting its entries towards `limit`.
+    ///
+    /// The table may already hold more than `limit` entries,
+    /// in which case new entries are refused until enough are removed.
+    pub fn from_tab(tab: RegTab<T>, limit: usize) -> Self {
+        Self {
+            len: AtomicUsize::new(tab.len()),
+            tab: ReadTab::new(tab),
+            limit,
+        }
+    }
+
+    /// Insert an entry, returning the value it replaced,
+    /// or an error if it is new and the table is full.
+    pub fn try_insert(&self, id: Id<T>, value: T) -> Result<Option<T>, LimitExceededError> {
+        match self.tab.inner().entry(id.as_str()) {
+            Entry::Occupied(mut e) => Ok(Some(e.insert(value))),
+            Entry::Vacant(e) => {
+                self.len
+                    .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |n| {
+                        (n < self.limit).then(|| n + 1)
+                    })
+                    .map_err(|_| LimitExceededError { limit: self.limit })?;
+                e.insert(value);
+                Ok(None)
+            }
+        }
+    }
+
+    /// Insert a batch of entries, stopping at the first one exceeding the limit.
+    ///
+    /// Entries before the failing one stay inserted.
+    pub fn try_extend<I: IntoIterator<Item = (Id<T>, T)>>(
+        &self,
+        iter: I,
+    ) -> Result<(), LimitExceededError> {
+        for (k, v) in iter {
+            self.try_insert(k, v)?;
+        }
+        Ok(())
+    }
+
+    /// Remove an entry, returning its value.
+    pub fn remove(&self, id: &str) -> Option<T> {
+        let (_, v) = self.tab.inner().remove(id)?;
+        // The count never drops below zero, even if it somehow lost track of an entry.
+        let _ = self
+            .len
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |n| n.checked_sub(1));
+        Some(v)
+    }
+
+    /// The maximum number of entries.
+    pub fn limit(&self) -> usize {
+        self.limit
+    }
+
+    /// Unwrap into the underlying table, lifting the limit.
+    pub fn into_tab(self) -> RegTab<T> {
+        self.tab.into_inner()
+    }
+}
+
+impl<T: Register> Deref for LimitedTab<T> {
+    type Target = ReadTab<T>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.tab
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn errors_past_limit() {
+        let tab = LimitedTab::with_limit(2);
+        tab.try_extend([(Id::new("core:a"), 1), (Id::new("core:b"), 2)])
+            .unwrap();
+        assert_eq!(tab.try_insert(Id::new("core:a"), 3), Ok(Some(1)));
+        assert_eq!(
+            tab.try_insert(Id::new("core:c"), 4),
+            Err(LimitExceededError { limit: 2 })
+        );
+        assert!(tab.get("core:c").is_none());
+        tab.remove("core:b");
+        assert_eq!(tab.try_insert(Id::new("core:c"), 4), Ok(None));
+        assert_eq!(tab.len(), 2);
+    }
+
+    #[test]
+    fn remove_adopted_entries() {
+        let tab = LimitedTab::from_tab(crate::regtab! { u32; "core:a" => 1, "core:b" => 2 }, 1);
+        assert!(tab.try_insert(Id::new("core:c"), 3).is_err());
+        assert_eq!(tab.remove("core:a"), Some(1));
+        assert_eq!(tab.remove("core:a"), None);
+        assert!(tab.try_insert(Id::new("core:c"), 3).is_err());
+        assert_eq!(tab.remove("core:b"), Some(2));
+        assert_eq!(tab.remove("core:x"), None);
+        assert_eq!(tab.try_insert(Id::new("core:c"), 3), Ok(None));
+        assert!(tab.try_insert(Id::new("core:d"), 4).is_err());
+        assert_eq!(tab.len(), 1);
+    }
+}
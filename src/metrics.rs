@@ -0,0 +1,89 @@
+use std::{
+    collections::HashMap,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+use dashmap::{mapref::one::Ref, DashMap};
+
+use super::{RegTab, Register};
+
+/// A registry table wrapper counting lookups, see `RegTab::with_metrics`.
+///
+/// Only lookups through this wrapper are counted;
+/// the table itself stays untouched and costs nothing extra when not wrapped.
+pub struct MeteredTab<'a, T: Register> {
+    tab: &'a RegTab<T>,
+    hits: AtomicUsize,
+    misses: AtomicUsize,
+    freq: DashMap<&'static str, usize>,
+}
+
+/// Lookup counts collected by a `MeteredTab`.
+#[derive(Clone, Debug)]
+pub struct RegTabMetrics {
+    /// Number of lookups finding an entry.
+    pub hits: usize,
+    /// Number of lookups finding nothing.
+    pub misses: usize,
+    /// Number of hits for each id.
+    pub per_id: HashMap<&'static str, usize>,
+}
+
+impl<'a, T: Register> MeteredTab<'a, T> {
+    /// Wrap a registry table with zeroed counters.
+    pub fn new(tab: &'a RegTab<T>) -> Self {
+        Self {
+            tab,
+            hits: AtomicUsize::new(0),
+            misses: AtomicUsize::new(0),
+            freq: DashMap::new(),
+        }
+    }
+
+    /// Look up an entry, counting a hit or miss.
+    pub fn get(&self, id: &str) -> Option<Ref<'a, &'static str, T>> {
+        let res = self.tab.get(id);
+        match &res {
+            Some(r) => {
+                self.hits.fetch_add(1, Ordering::Relaxed);
+                *self.freq.entry(*r.key()).or_insert(0) += 1;
+            }
+            None => {
+                self.misses.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+        res
+    }
+
+    /// The wrapped registry table.
+    pub fn tab(&self) -> &'a RegTab<T> {
+        self.tab
+    }
+
+    /// Take a snapshot of the counters.
+    pub fn metrics(&self) -> RegTabMetrics {
+        RegTabMetrics {
+            hits: self.hits.load(Ordering::Relaxed),
+            misses: self.misses.load(Ordering::Relaxed),
+            per_id: self.freq.iter().map(|r| (*r.key(), *r.value())).collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn counts_lookups() {
+        let tab = crate::regtab! { u32; "core:a" => 1, "core:b" => 2, "core:c" => 3 };
+        let metered = tab.with_metrics();
+        for id in ["core:a", "core:b", "core:a", "core:x", "core:a", ""] {
+            metered.get(id);
+        }
+        let m = metered.metrics();
+        assert_eq!((m.hits, m.misses), (4, 2));
+        assert_eq!(m.per_id.get("core:a"), Some(&3));
+        assert_eq!(m.per_id.get("core:b"), Some(&1));
+        assert_eq!(m.per_id.get("core:c"), None);
+        assert_eq!(m.per_id.len(), 2);
+    }
+}
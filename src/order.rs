@@ -0,0 +1,104 @@
+use std::ops::Deref;
+
+use dashmap::mapref::one::Ref;
+
+use super::{Id, ReadTab, RegTab, Register};
+
+/// A registry table remembering the order ids were first inserted in.
+///
+/// The order is kept in a vector beside the table, costing one `&'static str` per entry.
+/// The table is only writable through this wrapper, which takes `&mut self` unlike `RegTab`.
+pub struct OrderedTab<T: Register> {
+    tab: ReadTab<T>,
+    order: Vec<&'static str>,
+}
+
+impl<T: Register> OrderedTab<T> {
+    /// Create an empty table.
+    pub fn new() -> Self {
+        Self {
+            tab: ReadTab::new(RegTab::new()),
+            order: vec![],
+        }
+    }
+
+    /// Insert an entry, returning the value it replaced.
+    ///
+    /// Replacing an entry keeps its original position.
+    pub fn insert(&mut self, id: Id<T>, value: T) -> Option<T> {
+        let old = self.tab.inner().insert(id.as_str(), value);
+        if old.is_none() {
+            self.order.push(id.as_str());
+        }
+        old
+    }
+
+    /// Remove an entry, returning its value.
+    pub fn remove(&mut self, id: &str) -> Option<T> {
+        let (_, v) = self.tab.inner().remove(id)?;
+        self.order.retain(|k| *k != id);
+        Some(v)
+    }
+
+    /// Iterate over the entries in insertion order.
+    pub fn iter_in_order(&self) -> impl Iterator<Item = (Id<T>, Ref<'_, &'static str, T>)> {
+        self.order
+            .iter()
+            .filter_map(|k| self.tab.get(k).map(|r| (Id::from_key(k), r)))
+    }
+
+    /// The ids in insertion order.
+    pub fn ids_in_order(&self) -> impl Iterator<Item = Id<T>> + '_ {
+        self.order.iter().map(|k| Id::from_key(k))
+    }
+
+    /// Unwrap into the underlying table, discarding the order.
+    pub fn into_tab(self) -> RegTab<T> {
+        self.tab.into_inner()
+    }
+}
+
+impl<T: Register> Deref for OrderedTab<T> {
+    type Target = ReadTab<T>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.tab
+    }
+}
+
+impl<T: Register> Default for OrderedTab<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: Register> FromIterator<(Id<T>, T)> for OrderedTab<T> {
+    fn from_iter<I: IntoIterator<Item = (Id<T>, T)>>(iter: I) -> Self {
+        let mut tab = Self::new();
+        for (k, v) in iter {
+            tab.insert(k, v);
+        }
+        tab
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keeps_insertion_order() {
+        let mut tab = OrderedTab::new();
+        for (k, v) in [("core:c", 1), ("core:a", 2), ("core:b", 3)] {
+            tab.insert(Id::new(k), v);
+        }
+        assert_eq!(tab.insert(Id::new("core:c"), 4), Some(1));
+        assert_eq!(tab.remove("core:a"), Some(2));
+        tab.insert(Id::new("core:a"), 5);
+        let entries: Vec<_> = tab.iter_in_order().map(|(k, v)| (k.as_str(), *v)).collect();
+        assert_eq!(entries, [("core:c", 4), ("core:b", 3), ("core:a", 5)]);
+        let ids: Vec<_> = tab.ids_in_order().map(|k| k.as_str()).collect();
+        assert_eq!(ids, ["core:c", "core:b", "core:a"]);
+        assert_eq!(tab.len(), 3);
+    }
+}
@@ -1,2 +1,6 @@
-pub use super::id::Id;
-pub use super::tab::{HasRegTab, RegTab};
+pub use super::id::{CaseFoldId, Id, IdFormat};
+pub use super::limit::LimitedTab;
+pub use super::order::OrderedTab;
+pub use super::set::IdSet;
+pub use super::source::SourcedTab;
+pub use super::tab::{BackedTab, HasRefs, HasRegTab, HeapSize, LayeredTab, ReadTab, RegTab};
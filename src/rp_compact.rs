@@ -0,0 +1,117 @@
+//! Compact serialization of `Rp` lists, for use with `#[serde(with = "fe3o4::rp_compact")]`.
+//!
+//! A list of only registered pointers is written as a plain array of id strings,
+//! such as `["core:iron", "core:gold"]`.
+//! If any element is an orphan, the whole list falls back to the tagged form of `Rp`,
+//! such as `[{"r": "core:iron"}, {"o": ...}]`.
+//! Both forms are accepted when deserializing, as are plain ids mixed with tagged elements.
+
+use serde::{
+    de::{DeserializeOwned, Error, Unexpected},
+    ser::SerializeSeq,
+    Deserialize, Deserializer, Serialize, Serializer,
+};
+
+use super::{HasRegTab, Rp};
+
+/// Serialize a list of `Rp`, compactly if all elements are registered.
+pub fn serialize<S, T>(value: &[Rp<T>], serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+    T: Clone + Serialize + HasRegTab,
+{
+    if value.iter().any(|rp| matches!(rp, Rp::Orphan(_))) {
+        return value.serialize(serializer);
+    }
+    let mut seq = serializer.serialize_seq(Some(value.len()))?;
+    for rp in value {
+        if let Rp::Registered(r) = rp {
+            seq.serialize_element(r.key())?;
+        }
+    }
+    seq.end()
+}
+
+#[derive(Deserialize)]
+#[serde(untagged, bound = "T: DeserializeOwned + HasRegTab")]
+enum Element<T: HasRegTab> {
+    Id(String),
+    Tagged(Rp<T>),
+}
+
+/// Deserialize a list of `Rp` in either the compact or the tagged form.
+pub fn deserialize<'de, D, T>(deserializer: D) -> Result<Vec<Rp<T>>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: DeserializeOwned + HasRegTab,
+{
+    Vec::<Element<T>>::deserialize(deserializer)?
+        .into_iter()
+        .map(|e| match e {
+            Element::Id(id) => Rp::lookup(&id).ok_or_else(|| {
+                D::Error::invalid_value(Unexpected::Str(&id), &"an already registered id string")
+            }),
+            Element::Tagged(rp) => Ok(rp),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+    struct Ore(u32);
+
+    crate::def_regtab!(Ore, ORES);
+
+    #[derive(PartialEq, Serialize, Deserialize)]
+    struct Vein {
+        #[serde(with = "crate::rp_compact")]
+        ores: Vec<Rp<Ore>>,
+    }
+
+    fn registered(id: &str) -> Rp<Ore> {
+        Rp::lookup(id).unwrap()
+    }
+
+    fn setup() {
+        Ore::reg_tab().insert("core:iron", Ore(1));
+        Ore::reg_tab().insert("core:gold", Ore(2));
+    }
+
+    #[test]
+    fn round_trip_registered() {
+        setup();
+        let vein = Vein {
+            ores: vec![registered("core:iron"), registered("core:gold")],
+        };
+        let json = serde_json::to_string(&vein).unwrap();
+        assert_eq!(json, r#"{"ores":["core:iron","core:gold"]}"#);
+        assert!(serde_json::from_str::<Vein>(&json).unwrap() == vein);
+    }
+
+    #[test]
+    fn round_trip_with_orphan() {
+        setup();
+        let vein = Vein {
+            ores: vec![registered("core:iron"), Rp::orphan(Ore(3))],
+        };
+        let json = serde_json::to_string(&vein).unwrap();
+        assert_eq!(json, r#"{"ores":[{"r":"core:iron"},{"o":3}]}"#);
+        assert!(serde_json::from_str::<Vein>(&json).unwrap() == vein);
+    }
+
+    #[test]
+    fn round_trip_empty() {
+        let vein = Vein { ores: vec![] };
+        let json = serde_json::to_string(&vein).unwrap();
+        assert_eq!(json, r#"{"ores":[]}"#);
+        assert!(serde_json::from_str::<Vein>(&json).unwrap() == vein);
+    }
+
+    #[test]
+    fn unregistered_id() {
+        assert!(serde_json::from_str::<Vein>(r#"{"ores":["core:tin"]}"#).is_err());
+    }
+}
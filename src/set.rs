@@ -0,0 +1,220 @@
+use std::{cmp::Ordering, slice::Iter};
+
+use super::{Id, Register};
+
+/// A set of ids stored as a sorted vector.
+///
+/// Membership is checked by binary search, and set operations merge in linear time.
+/// This is more compact than a `HashSet` for many small sets, but inserting is linear to its size.
+pub struct IdSet<T: Register>(Vec<Id<T>>);
+
+impl<T: Register> IdSet<T> {
+    /// Create an empty set.
+    pub fn new() -> Self {
+        Self(vec![])
+    }
+
+    fn search(&self, id: &Id<T>) -> Result<usize, usize> {
+        self.0.binary_search_by(|x| x.as_str().cmp(id.as_str()))
+    }
+
+    /// Add an id, returning whether it was absent.
+    pub fn insert(&mut self, id: Id<T>) -> bool {
+        match self.search(&id) {
+            Ok(_) => false,
+            Err(i) => {
+                self.0.insert(i, id);
+                true
+            }
+        }
+    }
+
+    /// Remove an id, returning whether it was present.
+    pub fn remove(&mut self, id: &Id<T>) -> bool {
+        match self.search(id) {
+            Ok(i) => {
+                self.0.remove(i);
+                true
+            }
+            Err(_) => false,
+        }
+    }
+
+    /// Whether the set contains an id.
+    pub fn contains(&self, id: &Id<T>) -> bool {
+        self.search(id).is_ok()
+    }
+
+    /// Number of ids in the set.
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    /// Whether the set is empty.
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Iterate over the ids in order.
+    pub fn iter(&self) -> Iter<'_, Id<T>> {
+        self.0.iter()
+    }
+
+    /// Ids in either set.
+    pub fn union(&self, other: &Self) -> Self {
+        let (a, b) = (&self.0, &other.0);
+        let mut res = Vec::with_capacity(a.len().max(b.len()));
+        let (mut i, mut j) = (0, 0);
+        while i < a.len() && j < b.len() {
+            match a[i].as_str().cmp(b[j].as_str()) {
+                Ordering::Less => {
+                    res.push(a[i]);
+                    i += 1;
+                }
+                Ordering::Greater => {
+                    res.push(b[j]);
+                    j += 1;
+                }
+                Ordering::Equal => {
+                    res.push(a[i]);
+                    i += 1;
+                    j += 1;
+                }
+            }
+        }
+        res.extend_from_slice(&a[i..]);
+        res.extend_from_slice(&b[j..]);
+        Self(res)
+    }
+
+    /// Ids in both sets.
+    pub fn intersection(&self, other: &Self) -> Self {
+        let (a, b) = (&self.0, &other.0);
+        let mut res = vec![];
+        let (mut i, mut j) = (0, 0);
+        while i < a.len() && j < b.len() {
+            match a[i].as_str().cmp(b[j].as_str()) {
+                Ordering::Less => i += 1,
+                Ordering::Greater => j += 1,
+                Ordering::Equal => {
+                    res.push(a[i]);
+                    i += 1;
+                    j += 1;
+                }
+            }
+        }
+        Self(res)
+    }
+}
+
+impl<T: Register> Clone for IdSet<T> {
+    fn clone(&self) -> Self {
+        Self(self.0.clone())
+    }
+}
+
+impl<T: Register> Default for IdSet<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: Register> PartialEq for IdSet<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.0 == other.0
+    }
+}
+
+impl<T: Register> Eq for IdSet<T> {}
+
+impl<T: Register> FromIterator<Id<T>> for IdSet<T> {
+    fn from_iter<I: IntoIterator<Item = Id<T>>>(iter: I) -> Self {
+        let mut v: Vec<_> = iter.into_iter().collect();
+        v.sort_unstable_by_key(|i| i.as_str());
+        v.dedup();
+        Self(v)
+    }
+}
+
+impl<'a, T: Register> IntoIterator for &'a IdSet<T> {
+    type Item = &'a Id<T>;
+    type IntoIter = Iter<'a, Id<T>>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use super::*;
+
+    fn set(ids: &[&'static str]) -> IdSet<()> {
+        ids.iter().map(|i| Id::new(i)).collect()
+    }
+
+    fn strs(set: &IdSet<()>) -> Vec<&'static str> {
+        set.iter().map(|i| i.as_str()).collect()
+    }
+
+    #[test]
+    fn insert_remove_keep_order() {
+        let mut s = IdSet::new();
+        assert!(s.insert(Id::<()>::new("core:c")));
+        assert!(s.insert(Id::new("core:a")));
+        assert!(s.insert(Id::new("core:b")));
+        assert!(!s.insert(Id::new("core:a")));
+        assert_eq!(strs(&s), ["core:a", "core:b", "core:c"]);
+        assert!(s.remove(&Id::new("core:b")));
+        assert!(!s.remove(&Id::new("core:b")));
+        assert_eq!(strs(&s), ["core:a", "core:c"]);
+        assert!(s.contains(&Id::new("core:c")) && !s.contains(&Id::new("core:b")));
+    }
+
+    #[test]
+    fn from_iter_dedups() {
+        let s = set(&["core:b", "core:a", "core:b", "core:a"]);
+        assert_eq!(strs(&s), ["core:a", "core:b"]);
+        assert!(set(&[]).is_empty());
+    }
+
+    #[test]
+    fn union_and_intersection() {
+        let a = set(&["core:a", "core:b", "core:d"]);
+        let b = set(&["core:b", "core:c", "core:d", "core:e"]);
+        assert_eq!(
+            strs(&a.union(&b)),
+            ["core:a", "core:b", "core:c", "core:d", "core:e"]
+        );
+        assert_eq!(strs(&a.intersection(&b)), ["core:b", "core:d"]);
+        assert!(a.union(&IdSet::new()) == a);
+        assert!(a.intersection(&IdSet::new()).is_empty());
+    }
+
+    #[test]
+    fn agrees_with_hash_set() {
+        let ids: Vec<&'static str> = (0..200)
+            .map(|i| &*Box::leak(format!("core:n{}", i * 7 % 50).into_boxed_str()))
+            .collect();
+        let (mut s, mut h) = (IdSet::<()>::new(), HashSet::new());
+        for (n, i) in ids.iter().enumerate() {
+            if n % 3 == 2 {
+                assert_eq!(s.remove(&Id::new(i)), h.remove(i));
+            } else {
+                assert_eq!(s.insert(Id::new(i)), h.insert(*i));
+            }
+        }
+        assert_eq!(s.len(), h.len());
+        assert!(ids.iter().all(|i| s.contains(&Id::new(i)) == h.contains(i)));
+        let other: HashSet<_> = ids[..60].iter().copied().collect();
+        let t: IdSet<()> = other.iter().map(|i| Id::new(i)).collect();
+        let mut union: Vec<_> = h.union(&other).copied().collect();
+        union.sort_unstable();
+        let mut inter: Vec<_> = h.intersection(&other).copied().collect();
+        inter.sort_unstable();
+        assert_eq!(strs(&s.union(&t)), union);
+        assert_eq!(strs(&s.intersection(&t)), inter);
+    }
+}
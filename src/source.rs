@@ -0,0 +1,140 @@
+use std::ops::Deref;
+
+use dashmap::{
+    mapref::{entry::Entry, one::Ref},
+    DashMap,
+};
+
+use super::{Id, ReadTab, RegTab, Register};
+
+/// A registry table recording which source defined each entry.
+///
+/// A source is any identifier of where an entry came from, such as a file path or mod name.
+/// Entries inserted with `insert` have no source, so users not needing provenance
+/// pay only for an empty map. Overwriting an entry replaces its source as well.
+///
+/// The entry is locked while its source is updated, so concurrent writes to the same id
+/// never leave the value of one paired with the source of another.
+pub struct SourcedTab<T: Register, S = &'static str> {
+    tab: ReadTab<T>,
+    sources: DashMap<&'static str, S>,
+}
+
+impl<T: Register, S> SourcedTab<T, S> {
+    /// Create an empty table.
+    pub fn new() -> Self {
+        Self {
+            tab: ReadTab::new(RegTab::new()),
+            sources: DashMap::new(),
+        }
+    }
+
+    /// Insert an entry without a source, returning the value it replaced.
+    pub fn insert(&self, id: Id<T>, value: T) -> Option<T> {
+        self.put(id, value, None)
+    }
+
+    /// Insert an entry defined by `source`, returning the value it replaced.
+    pub fn insert_from(&self, id: Id<T>, value: T, source: S) -> Option<T> {
+        self.put(id, value, Some(source))
+    }
+
+    fn put(&self, id: Id<T>, value: T, source: Option<S>) -> Option<T> {
+        let entry = self.tab.inner().entry(id.as_str());
+        match source {
+            Some(s) => self.sources.insert(id.as_str(), s),
+            None => self.sources.remove(id.as_str()).map(|(_, s)| s),
+        };
+        match entry {
+            Entry::Occupied(mut e) => Some(e.insert(value)),
+            Entry::Vacant(e) => {
+                e.insert(value);
+                None
+            }
+        }
+    }
+
+    /// Remove an entry and its source, returning its value.
+    pub fn remove(&self, id: &str) -> Option<T> {
+        self.tab
+            .inner()
+            .remove_if(id, |k, _| {
+                self.sources.remove(*k);
+                true
+            })
+            .map(|(_, v)| v)
+    }
+
+    /// The source that defined an entry, if it was inserted with one.
+    pub fn source_of(&self, id: &str) -> Option<Ref<'_, &'static str, S>> {
+        self.sources.get(id)
+    }
+
+    /// Unwrap into the underlying table, discarding the sources.
+    pub fn into_tab(self) -> RegTab<T> {
+        self.tab.into_inner()
+    }
+}
+
+impl<T: Register, S> Deref for SourcedTab<T, S> {
+    type Target = ReadTab<T>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.tab
+    }
+}
+
+impl<T: Register, S> Default for SourcedTab<T, S> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn records_latest_source() {
+        let tab = SourcedTab::new();
+        tab.insert_from(Id::new("core:iron"), 1, "base.toml");
+        tab.insert_from(Id::new("core:gold"), 2, "base.toml");
+        assert_eq!(
+            tab.insert_from(Id::new("core:iron"), 3, "mod.toml"),
+            Some(1)
+        );
+        tab.insert(Id::new("core:gold"), 4);
+        assert_eq!(*tab.source_of("core:iron").unwrap(), "mod.toml");
+        assert_eq!(*tab.get("core:iron").unwrap(), 3);
+        assert!(tab.source_of("core:gold").is_none());
+        assert!(tab.source_of("core:tin").is_none());
+    }
+
+    #[test]
+    fn remove_drops_source() {
+        let tab = SourcedTab::new();
+        tab.insert_from(Id::new("core:iron"), 1, "base.toml");
+        assert_eq!(tab.remove("core:iron"), Some(1));
+        assert!(tab.source_of("core:iron").is_none());
+        assert_eq!(tab.remove("core:iron"), None);
+    }
+
+    #[test]
+    fn concurrent_overwrites_stay_paired() {
+        let tab = SourcedTab::new();
+        std::thread::scope(|s| {
+            for i in 0..8 {
+                let tab = &tab;
+                s.spawn(move || {
+                    for _ in 0..1000 {
+                        tab.insert_from(Id::new("core:iron"), i, i);
+                    }
+                });
+            }
+        });
+        assert_eq!(
+            *tab.get("core:iron").unwrap(),
+            *tab.source_of("core:iron").unwrap()
+        );
+    }
+}
@@ -1,10 +1,23 @@
-use std::ops::{Deref, DerefMut};
+use std::{
+    collections::{HashMap, HashSet},
+    hash::{BuildHasher, Hash},
+    iter::Map,
+    mem::size_of,
+    ops::{Deref, DerefMut},
+    sync::Arc,
+};
 
-use dashmap::DashMap;
+use dashmap::{
+    iter::{Iter, OwningIter},
+    mapref::{entry::Entry, one::Ref},
+    DashMap, SharedValue,
+};
 
-use super::Register;
+use super::{err::FromPartsError, metrics::MeteredTab, Id, Register};
 
 /// A registry table to store mappings from string IDs to entries.
+///
+/// Keys are not checked like `Id::new` does, so ids of stored entries may have empty parts.
 pub struct RegTab<T: Register>(DashMap<&'static str, T>);
 
 impl<T: Register> RegTab<T> {
@@ -12,10 +25,553 @@ impl<T: Register> RegTab<T> {
     pub fn new() -> Self {
         Self(DashMap::new())
     }
+
+    /// Insert a batch of entries, later entries overwriting earlier ones with the same id.
+    ///
+    /// Entries are grouped by shard first, so each shard is write-locked once for the whole batch
+    /// rather than once per entry.
+    pub fn insert_many<I: IntoIterator<Item = (Id<T>, T)>>(&self, iter: I) {
+        let hash = |k: &&'static str| self.0.hasher().hash_one(k);
+        let shards = self.0.shards();
+        let mut groups: Vec<Vec<_>> = shards.iter().map(|_| vec![]).collect();
+        for (k, v) in iter {
+            let k = k.as_str();
+            let h = hash(&k);
+            groups[self.0.determine_shard(h as usize)].push((h, k, v));
+        }
+        for (shard, group) in shards.iter().zip(groups) {
+            if group.is_empty() {
+                continue;
+            }
+            let mut shard = shard.write();
+            for (h, k, v) in group {
+                match shard.get_mut(h, |(x, _)| *x == k) {
+                    Some((_, old)) => *old.get_mut() = v,
+                    None => {
+                        shard.insert(h, (k, SharedValue::new(v)), |(x, _)| hash(x));
+                    }
+                }
+            }
+        }
+    }
+
+    /// Create a table pairing ids with values at the same positions.
+    ///
+    /// Duplicate ids are rejected rather than overwritten, since column-wise data should not repeat keys.
+    pub fn from_parts(ids: Vec<Id<T>>, values: Vec<T>) -> Result<Self, FromPartsError> {
+        if ids.len() != values.len() {
+            return Err(FromPartsError::LengthMismatch {
+                ids: ids.len(),
+                values: values.len(),
+            });
+        }
+        let tab = Self::new();
+        for (k, v) in ids.into_iter().zip(values) {
+            match tab.entry(k.as_str()) {
+                Entry::Occupied(_) => return Err(FromPartsError::DuplicateId(k.as_str())),
+                Entry::Vacant(e) => {
+                    e.insert(v);
+                }
+            }
+        }
+        Ok(tab)
+    }
+
+    /// Whether every id is present in the table.
+    pub fn contains_all(&self, ids: &[Id<T>]) -> bool {
+        ids.iter().all(|i| self.contains_key(i.as_str()))
+    }
+
+    /// Ids absent in the table, in the order given.
+    pub fn missing<'a>(&self, ids: &'a [Id<T>]) -> Vec<&'a Id<T>> {
+        ids.iter()
+            .filter(|i| !self.contains_key(i.as_str()))
+            .collect()
+    }
+
+    /// Apply `f` to every entry matching `pred`.
+    ///
+    /// Shards are write-locked one at a time, so the update is not atomic across the table:
+    /// concurrent readers may observe some entries updated and others not.
+    /// `pred` and `f` run while a shard is write-locked, so accessing this table from them deadlocks.
+    pub fn update_where(&self, pred: impl Fn(&Id<T>, &T) -> bool, f: impl Fn(&mut T)) {
+        for mut r in self.iter_mut() {
+            if pred(&Id::from_key(r.key()), r.value()) {
+                f(r.value_mut());
+            }
+        }
+    }
+
+    /// Wrap the table to count lookups made through the wrapper.
+    pub fn with_metrics(&self) -> MeteredTab<'_, T> {
+        MeteredTab::new(self)
+    }
+
+    /// Move entries of `other` into this table, combining values present in both with `resolve`.
+    ///
+    /// `resolve` receives the id, the existing value and the incoming value, in that order.
+    pub fn merge_with(&mut self, other: Self, resolve: impl Fn(&Id<T>, T, T) -> T) {
+        for (k, v) in other {
+            let v = match self.remove(k.as_str()) {
+                Some((_, old)) => resolve(&k, old, v),
+                None => v,
+            };
+            self.insert(k.as_str(), v);
+        }
+    }
+
+    /// Look up an entry, returning the stored id along with the value.
+    pub fn get_key_value(&self, id: &str) -> Option<(Id<T>, Ref<'_, &'static str, T>)> {
+        self.get(id).map(|r| (Id::from_key(r.key()), r))
+    }
+
+    /// All ids in the table, sorted with `Id::cmp_natural`.
+    pub fn ids_natural(&self) -> Vec<Id<T>> {
+        let mut ids: Vec<Id<T>> = self.iter().map(|r| Id::from_key(r.key())).collect();
+        ids.sort_by(Id::cmp_natural);
+        ids
+    }
+
+    /// Copy the entries into a vector of id strings and values, sorted by id.
+    pub fn to_vec(&self) -> Vec<(String, T)>
+    where
+        T: Clone,
+    {
+        let mut v: Vec<_> = self
+            .iter()
+            .map(|r| (r.key().to_string(), r.value().clone()))
+            .collect();
+        v.sort_by(|a, b| a.0.cmp(&b.0));
+        v
+    }
+
+    /// Up to `limit` ids starting with `prefix`, sorted.
+    pub fn complete(&self, prefix: &str, limit: usize) -> Vec<Id<T>> {
+        let mut ids: Vec<Id<T>> = self
+            .iter()
+            .filter(|r| r.key().starts_with(prefix))
+            .map(|r| Id::from_key(r.key()))
+            .collect();
+        ids.sort_unstable_by_key(|i| i.as_str());
+        ids.truncate(limit);
+        ids
+    }
+
+    /// Up to `limit` distinct module names starting with `prefix`, sorted.
+    ///
+    /// Use this while the input has no `:` yet, and `complete` afterwards.
+    pub fn complete_modules(&self, prefix: &str, limit: usize) -> Vec<&'static str> {
+        let mut mods: Vec<_> = self
+            .iter()
+            .map(|r| Id::<T>::from_key(r.key()).modname())
+            .filter(|m| m.starts_with(prefix))
+            .collect();
+        mods.sort_unstable();
+        mods.dedup();
+        mods.truncate(limit);
+        mods
+    }
+
+    /// Up to `limit` ids fuzzily matching `query` with their scores, best first.
+    ///
+    /// An id matches if it contains the characters of `query` in order, ignoring whitespace and ASCII case.
+    /// Consecutive matches and matches at the start of a part or word score higher,
+    /// and gaps between matches score lower.
+    /// Matches in the same case score slightly higher, breaking ties between ids differing in case or separators.
+    /// An empty query matches every id with score 0.
+    #[cfg(feature = "fuzzy")]
+    pub fn fuzzy_search(&self, query: &str, limit: usize) -> Vec<(Id<T>, i64)> {
+        let mut res: Vec<_> = self
+            .iter()
+            .filter_map(|r| fuzzy_score(query, r.key()).map(|s| (Id::from_key(r.key()), s)))
+            .collect();
+        res.sort_unstable_by(|(a, x), (b, y)| y.cmp(x).then_with(|| a.as_str().cmp(b.as_str())));
+        res.truncate(limit);
+        res
+    }
+
+    /// Convert into a table of shared values, storing equal values only once.
+    ///
+    /// Values become `Arc<T>`; the number of values collapsed into an existing one is also returned.
+    pub fn dedup(self) -> (RegTab<Arc<T>>, usize)
+    where
+        T: Hash + Eq,
+    {
+        let mut seen: HashSet<Arc<T>> = HashSet::new();
+        let mut dups = 0;
+        let tab = self
+            .into_iter()
+            .map(|(k, v)| match seen.get(&v) {
+                Some(a) => {
+                    dups += 1;
+                    (k.cast(), a.clone())
+                }
+                None => {
+                    let a = Arc::new(v);
+                    seen.insert(a.clone());
+                    (k.cast(), a)
+                }
+            })
+            .collect();
+        (tab, dups)
+    }
+
+    /// Leak the table to get a reference living for the rest of the process.
+    ///
+    /// This is meant for creating the global table returned by `HasRegTab::reg_tab` once at startup.
+    /// The memory is never freed.
+    pub fn into_static(self) -> &'static Self {
+        Box::leak(Box::new(self))
+    }
+
+    /// Create a new registry table containing clones of the entries matching `f`.
+    ///
+    /// This clones every matching value, so it costs as much as copying that part of the table.
+    pub fn filter<F: Fn(&Id<T>, &T) -> bool>(&self, f: F) -> Self
+    where
+        T: Clone,
+    {
+        self.iter()
+            .filter(|r| f(&Id::from_key(r.key()), r.value()))
+            .map(|r| (Id::from_key(r.key()), r.value().clone()))
+            .collect()
+    }
+
+    /// Move the entries matching `pred` out into a new registry table.
+    ///
+    /// `pred` is called once per entry in unspecified order.
+    pub fn drain_filter(&mut self, mut pred: impl FnMut(&Id<T>, &T) -> bool) -> Self {
+        let ids: Vec<_> = self
+            .iter()
+            .filter(|r| pred(&Id::from_key(r.key()), r.value()))
+            .map(|r| *r.key())
+            .collect();
+        ids.into_iter()
+            .filter_map(|k| self.remove(k))
+            .map(|(k, v)| (Id::from_key(k), v))
+            .collect()
+    }
+
+    /// Find pairs of ids that are equal under ASCII case folding.
+    ///
+    /// With `fold_separators`, `-` and `_` are also considered equal.
+    /// All colliding pairs are returned, each ordered and sorted by id.
+    pub fn find_ambiguous(&self, fold_separators: bool) -> Vec<(Id<T>, Id<T>)> {
+        let mut groups: HashMap<String, Vec<&'static str>> = HashMap::new();
+        for r in self.iter() {
+            let mut folded = r.key().to_ascii_lowercase();
+            if fold_separators {
+                folded = folded.replace('-', "_");
+            }
+            groups.entry(folded).or_default().push(r.key());
+        }
+        let mut pairs = vec![];
+        for mut g in groups.into_values() {
+            g.sort_unstable();
+            for (i, a) in g.iter().enumerate() {
+                for b in &g[i + 1..] {
+                    pairs.push((Id::from_key(a), Id::from_key(b)));
+                }
+            }
+        }
+        pairs.sort_unstable_by_key(|(a, b)| (a.as_str(), b.as_str()));
+        pairs
+    }
+
+    /// Find references from entries to ids absent in the registry table of `U`.
+    ///
+    /// Every dangling reference is returned along with the id of the entry holding it.
+    pub fn dangling_refs<U: HasRegTab>(&self) -> Vec<(Id<T>, Id<U>)>
+    where
+        T: HasRefs<U>,
+    {
+        let tab = U::reg_tab();
+        self.iter()
+            .flat_map(|r| {
+                let k = Id::from_key(r.key());
+                r.refs().into_iter().map(move |i| (k, i))
+            })
+            .filter(|(_, i)| !tab.contains_key(i.as_str()))
+            .collect()
+    }
+
+    /// Count the entries grouped by a key computed from each value.
+    pub fn count_by<K: Eq + Hash, F: Fn(&T) -> K>(&self, key: F) -> HashMap<K, usize> {
+        let mut counts = HashMap::new();
+        for r in self.iter() {
+            *counts.entry(key(r.value())).or_insert(0) += 1;
+        }
+        counts
+    }
+
+    /// Compute statistics over the entries.
+    pub fn stats(&self) -> RegTabStats {
+        let mut per_module = HashMap::new();
+        let mut min_name_len = usize::MAX;
+        let mut max_name_len = 0;
+        let mut total_name_len = 0;
+        for r in self.iter() {
+            let id = Id::<T>::from_key(r.key());
+            *per_module.entry(id.modname()).or_insert(0) += 1;
+            let n = id.idname().len();
+            min_name_len = min_name_len.min(n);
+            max_name_len = max_name_len.max(n);
+            total_name_len += n;
+        }
+        let len = self.len();
+        RegTabStats {
+            len,
+            modules: per_module.len(),
+            per_module,
+            min_name_len: if len == 0 { 0 } else { min_name_len },
+            max_name_len,
+            avg_name_len: if len == 0 {
+                0.0
+            } else {
+                total_name_len as f64 / len as f64
+            },
+        }
+    }
+
+    /// Approximate memory used by the entries, in bytes.
+    ///
+    /// Only the inline sizes of keys and values are counted;
+    /// the `'static` key strings, heap data owned by values and the map's own overhead are not.
+    pub fn approx_size_bytes(&self) -> usize {
+        self.len() * (size_of::<&'static str>() + size_of::<T>())
+    }
+
+    /// Like `approx_size_bytes`, additionally counting heap data reported by `HeapSize`.
+    pub fn approx_size_bytes_deep(&self) -> usize
+    where
+        T: HeapSize,
+    {
+        self.approx_size_bytes() + self.iter().map(|r| r.heap_size()).sum::<usize>()
+    }
+}
+
+#[cfg(feature = "fuzzy")]
+fn fuzzy_score(query: &str, target: &str) -> Option<i64> {
+    let t = target.as_bytes();
+    let mut score = 0;
+    let mut pos = 0;
+    let mut prev = None;
+    for q in query.bytes().filter(|b| !b.is_ascii_whitespace()) {
+        let i = pos + t[pos..].iter().position(|c| c.eq_ignore_ascii_case(&q))?;
+        score += if t[i] == q { 2 } else { 1 };
+        match prev {
+            Some(p) if p + 1 == i => score += 5,
+            Some(p) => score -= (i - p - 1) as i64,
+            None => score -= i as i64,
+        }
+        if i == 0 || matches!(t[i - 1], b':' | b'_' | b'-' | b'.' | b'/') {
+            score += 3;
+        }
+        prev = Some(i);
+        pos = i + 1;
+    }
+    Some(score)
+}
+
+/// Statistics over entries of a registry table, see `RegTab::stats`.
+///
+/// Name lengths are all zero for an empty table.
+#[derive(Clone, Debug)]
+pub struct RegTabStats {
+    /// Number of entries.
+    pub len: usize,
+    /// Number of distinct modules.
+    pub modules: usize,
+    /// Number of entries in each module.
+    pub per_module: HashMap<&'static str, usize>,
+    /// Length of the shortest identifier part.
+    pub min_name_len: usize,
+    /// Length of the longest identifier part.
+    pub max_name_len: usize,
+    /// Average length of identifier parts.
+    pub avg_name_len: f64,
+}
+
+/// A stack of registry tables resolving lookups through each layer in turn.
+///
+/// Layers are consulted from the most recently pushed to the first,
+/// so an entry in a later layer overrides entries with the same id in earlier ones.
+/// Layers are borrowed, so global and scoped tables may be mixed.
+pub struct LayeredTab<'a, T: Register>(Vec<&'a RegTab<T>>);
+
+impl<'a, T: Register> LayeredTab<'a, T> {
+    /// Create a layered table without any layer.
+    pub fn new() -> Self {
+        Self(vec![])
+    }
+
+    /// Push a layer on top, overriding existing layers.
+    pub fn push(&mut self, tab: &'a RegTab<T>) {
+        self.0.push(tab);
+    }
+
+    /// Look up an entry in the topmost layer containing it.
+    pub fn get(&self, id: &str) -> Option<Ref<'a, &'static str, T>> {
+        self.0.iter().rev().find_map(|tab| tab.get(id))
+    }
+
+    /// Whether any layer contains the id.
+    pub fn contains_key(&self, id: &str) -> bool {
+        self.0.iter().any(|tab| tab.contains_key(id))
+    }
+}
+
+impl<T: Register> Default for LayeredTab<'_, T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// A registry table loading entries from a backing store on miss.
+///
+/// Loaded entries are cached in the table and never evicted.
+/// Concurrent misses on the same id may call the loader more than once,
+/// but only the first value inserted is kept and returned to all callers.
+pub struct BackedTab<T: Register> {
+    tab: RegTab<T>,
+    load: Loader<T>,
+}
+
+type Loader<T> = Box<dyn Fn(&Id<T>) -> Option<T> + Send + Sync>;
+
+impl<T: Register> BackedTab<T> {
+    /// Create an empty table backed by `load`.
+    pub fn new(load: impl Fn(&Id<T>) -> Option<T> + Send + Sync + 'static) -> Self {
+        Self {
+            tab: RegTab::new(),
+            load: Box::new(load),
+        }
+    }
+
+    /// Look up an entry, loading and caching it on miss.
+    pub fn get(&self, id: Id<T>) -> Option<Ref<'_, &'static str, T>> {
+        if let Some(r) = self.tab.get(id.as_str()) {
+            return Some(r);
+        }
+        let v = (self.load)(&id)?;
+        Some(self.tab.entry(id.as_str()).or_insert(v).downgrade())
+    }
+
+    /// The entries loaded so far.
+    pub fn cached(&self) -> &RegTab<T> {
+        &self.tab
+    }
+}
+
+/// A registry table only readable from outside, owned by wrappers guarding their writes.
+///
+/// `LimitedTab`, `OrderedTab` and `SourcedTab` keep bookkeeping beside their entries,
+/// which writes bypassing the wrapper would leave stale, so they dereference to this instead of `RegTab`.
+pub struct ReadTab<T: Register>(RegTab<T>);
+
+impl<T: Register> ReadTab<T> {
+    pub(crate) fn new(tab: RegTab<T>) -> Self {
+        Self(tab)
+    }
+
+    pub(crate) fn inner(&self) -> &RegTab<T> {
+        &self.0
+    }
+
+    pub(crate) fn into_inner(self) -> RegTab<T> {
+        self.0
+    }
+
+    /// Look up an entry.
+    pub fn get(&self, id: &str) -> Option<Ref<'_, &'static str, T>> {
+        self.0.get(id)
+    }
+
+    /// Whether an entry with the id exists.
+    pub fn contains_key(&self, id: &str) -> bool {
+        self.0.contains_key(id)
+    }
+
+    /// Number of entries.
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    /// Whether the table has no entries.
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Iterate over the entries in no particular order.
+    pub fn iter(&self) -> Iter<'_, &'static str, T> {
+        self.0.iter()
+    }
+}
+
+/// A type referring to registered items of type `U` by their ids.
+pub trait HasRefs<U: Register> {
+    /// Ids of the items this value refers to.
+    fn refs(&self) -> Vec<Id<U>>;
+}
+
+/// A type able to report the heap memory it owns.
+pub trait HeapSize {
+    /// Approximate number of bytes owned on heap, excluding `size_of::<Self>()`.
+    fn heap_size(&self) -> usize;
+}
+
+impl<T: Register> Clone for RegTab<T>
+where
+    T: Clone,
+{
+    /// Deep copy the table, cloning every entry.
+    fn clone(&self) -> Self {
+        Self(self.0.clone())
+    }
+}
+
+impl<T: Register> PartialEq for RegTab<T>
+where
+    T: PartialEq,
+{
+    /// Whether both tables hold the same ids mapped to equal values.
+    fn eq(&self, other: &Self) -> bool {
+        if std::ptr::eq(self, other) {
+            return true;
+        }
+        self.len() == other.len()
+            && self
+                .iter()
+                .all(|r| other.get(r.key()).is_some_and(|v| *v == *r))
+    }
+}
+
+impl<T: Register> IntoIterator for RegTab<T> {
+    type Item = (Id<T>, T);
+    type IntoIter = Map<OwningIter<&'static str, T>, fn((&'static str, T)) -> (Id<T>, T)>;
+
+    /// Consume the table, yielding owned entries in no particular order.
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.into_iter().map(|(k, v)| (Id::from_key(k), v))
+    }
+}
+
+impl<T: Register> FromIterator<(Id<T>, T)> for RegTab<T> {
+    /// Collect entries into a table, later entries overwriting earlier ones with the same id.
+    fn from_iter<I: IntoIterator<Item = (Id<T>, T)>>(iter: I) -> Self {
+        Self(iter.into_iter().map(|(k, v)| (k.as_str(), v)).collect())
+    }
 }
 
 /// A type having a registry table definition.
 pub trait HasRegTab: Register {
+    /// Whether an `Rp` to this type may be deserialized from an inline orphan.
+    ///
+    /// Set this to `false` for types that should only ever exist as registered entries,
+    /// so that deserializing an orphan fails instead of smuggling inline data in.
+    /// Orphans created in code with `Rp::orphan` are unaffected.
+    const ALLOW_ORPHANS: bool = true;
+
     /// Registry table for this type.
     fn reg_tab() -> &'static RegTab<Self>;
 }
@@ -35,10 +591,14 @@ impl<T: Register> DerefMut for RegTab<T> {
 }
 
 /// Implement `HasRegTab` for a certain type with provided registry table.
+///
+/// Append `allow_orphans = false` to forbid deserializing orphans, see `HasRegTab::ALLOW_ORPHANS`.
 #[macro_export]
 macro_rules! has_regtab {
-    ($t:ty,$e:expr) => {
+    ($t:ty,$e:expr $(, allow_orphans = $b:expr)?) => {
         impl $crate::HasRegTab for $t {
+            $(const ALLOW_ORPHANS: bool = $b;)?
+
             #[inline]
             fn reg_tab() -> &'static $crate::RegTab<Self> {
                 &$e
@@ -47,12 +607,47 @@ macro_rules! has_regtab {
     };
 }
 
+/// Create a registry table with entries, mostly for concise test fixtures.
+///
+/// The entry type may be specified before a `;`, in case it cannot be inferred.
+/// Later entries overwrite earlier ones with the same id.
+/// Ids must be constant string expressions, and are checked like `Id::new` at compile time,
+/// so an id with an empty part fails to build. Values may be computed at runtime.
+///
+/// # Example
+/// ```
+/// use fe3o4::{regtab, RegTab};
+///
+/// let tab = regtab! { u32; "core:one" => 1, "core:two" => 2 };
+/// assert_eq!(*tab.get("core:two").unwrap(), 2);
+/// let tab: RegTab<&str> = regtab! { "core:foo" => "foo" };
+/// assert_eq!(tab.len(), 1);
+/// ```
+///
+/// ```compile_fail
+/// let tab = fe3o4::regtab! { u32; "core:" => 1 };
+/// ```
+#[macro_export]
+macro_rules! regtab {
+    ($t:ty; $($k:expr => $v:expr),* $(,)?) => {{
+        let tab: $crate::RegTab<$t> = $crate::RegTab::new();
+        $(tab.insert(const { $crate::id::checked_key($k) }, $v);)*
+        tab
+    }};
+    ($($k:expr => $v:expr),* $(,)?) => {{
+        let tab = $crate::RegTab::new();
+        $(tab.insert(const { $crate::id::checked_key($k) }, $v);)*
+        tab
+    }};
+}
+
 /// Define a static variable as registry table for specified type,
 /// automatically calling `has_regtab`.
 ///
 /// `_REGTAB` is used as name of the variable unless an identifier is specified in arguments,
 /// which may lead to naming conflicts.
 /// In such case, supply a name manually.
+/// Like `has_regtab`, `allow_orphans = false` may follow the name.
 /// 
 /// # Example
 /// ```
@@ -79,9 +674,377 @@ macro_rules! def_regtab {
     ($t:ty) => {
         def_regtab!($t, _REGTAB);
     };
-    ($t:ty,$i:ident) => {
+    ($t:ty,$i:ident $(, allow_orphans = $b:expr)?) => {
         #[::static_init::dynamic]
         static $i: $crate::RegTab<$t> = $crate::RegTab::new();
-        $crate::has_regtab!($t, $i);
+        $crate::has_regtab!($t, $i $(, allow_orphans = $b)?);
     };
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn into_iter_map_collect() {
+        let tab = regtab! { u32; "core:one" => 1, "core:two" => 2 };
+        let doubled: RegTab<u32> = tab.into_iter().map(|(k, v)| (k, v * 2)).collect();
+        assert_eq!(doubled.len(), 2);
+        assert_eq!(*doubled.get("core:one").unwrap(), 2);
+        assert_eq!(*doubled.get("core:two").unwrap(), 4);
+    }
+
+    #[test]
+    fn layered_lookup_order() {
+        let base = regtab! { u32; "core:a" => 1, "core:b" => 2 };
+        let patch = regtab! { u32; "core:b" => 20, "core:c" => 30 };
+        let mut tab = LayeredTab::new();
+        tab.push(&base);
+        tab.push(&patch);
+        assert_eq!(*tab.get("core:a").unwrap(), 1);
+        assert_eq!(*tab.get("core:b").unwrap(), 20);
+        assert_eq!(*tab.get("core:c").unwrap(), 30);
+        assert!(tab.get("core:d").is_none());
+        assert!(tab.contains_key("core:a") && !tab.contains_key("core:d"));
+    }
+
+    #[test]
+    fn stats_fixture() {
+        let tab =
+            regtab! { (); "core:a" => (), "core:bcd" => (), "core:ef" => (), "deco:ghij" => () };
+        let stats = tab.stats();
+        assert_eq!(stats.len, 4);
+        assert_eq!(stats.modules, 2);
+        assert_eq!(stats.per_module["core"], 3);
+        assert_eq!(stats.per_module["deco"], 1);
+        assert_eq!(stats.min_name_len, 1);
+        assert_eq!(stats.max_name_len, 4);
+        assert_eq!(stats.avg_name_len, 2.5);
+    }
+
+    #[test]
+    fn stats_empty() {
+        let stats = regtab! { (); }.stats();
+        assert_eq!(stats.len, 0);
+        assert_eq!(stats.modules, 0);
+        assert!(stats.per_module.is_empty());
+        assert_eq!(stats.min_name_len, 0);
+        assert_eq!(stats.max_name_len, 0);
+        assert_eq!(stats.avg_name_len, 0.0);
+    }
+
+    #[test]
+    fn drain_filter_partitions() {
+        let mut tab = regtab! { u32; "core:a" => 1, "core:b" => 2, "core:c" => 3, "core:d" => 4 };
+        let even = tab.drain_filter(|_, v| v % 2 == 0);
+        assert_eq!(even.len(), 2);
+        assert_eq!(tab.len(), 2);
+        assert!(even.iter().all(|r| r.value() % 2 == 0));
+        assert!(tab.iter().all(|r| r.value() % 2 == 1));
+        assert!(even.iter().all(|r| !tab.contains_key(r.key())));
+    }
+
+    #[test]
+    fn empty_part_keys() {
+        let tab = RegTab::new();
+        tab.insert("core:", 1);
+        tab.insert(":stone", 2);
+        assert_eq!(tab.stats().len, 2);
+        assert_eq!(tab.complete("core", 10).len(), 1);
+        assert_eq!(tab.filter(|_, _| true).len(), 2);
+        assert_eq!(tab.into_iter().count(), 2);
+    }
+
+    #[test]
+    fn insert_many_batch() {
+        let tab = regtab! { u32; "core:a" => 0 };
+        let keys: Vec<&'static str> = (0..1000)
+            .map(|i| &*Box::leak(format!("core:n{i}").into_boxed_str()))
+            .collect();
+        tab.insert_many(keys.iter().map(|k| (Id::new(k), 1)));
+        tab.insert_many([
+            (Id::new("core:a"), 1),
+            (Id::new("core:b"), 2),
+            (Id::new("core:b"), 3),
+        ]);
+        assert_eq!(tab.len(), 1002);
+        assert_eq!(*tab.get("core:a").unwrap(), 1);
+        assert_eq!(*tab.get("core:b").unwrap(), 3);
+        assert!(keys.iter().all(|k| *tab.get(k).unwrap() == 1));
+        tab.insert("core:n0", 5);
+        assert_eq!(*tab.get("core:n0").unwrap(), 5);
+        assert_eq!(tab.len(), 1002);
+    }
+
+    #[test]
+    fn eq_same_entries() {
+        let a = regtab! { u32; "core:a" => 1, "core:b" => 2 };
+        let b = regtab! { u32; "core:b" => 2, "core:a" => 1 };
+        assert!(a == b);
+        assert!(a == a);
+        assert!(regtab! { u32; } == regtab! { u32; });
+    }
+
+    #[test]
+    fn eq_different_values() {
+        let a = regtab! { u32; "core:a" => 1, "core:b" => 2 };
+        let b = regtab! { u32; "core:a" => 1, "core:b" => 3 };
+        assert!(a != b);
+    }
+
+    #[test]
+    fn eq_different_keys() {
+        let a = regtab! { u32; "core:a" => 1, "core:b" => 2 };
+        let b = regtab! { u32; "core:a" => 1, "core:c" => 2 };
+        let c = regtab! { u32; "core:a" => 1 };
+        assert!(a != b);
+        assert!(a != c);
+        assert!(c != a);
+    }
+
+    #[cfg(feature = "fuzzy")]
+    fn fuzzy_fixture() -> RegTab<u32> {
+        regtab! { u32;
+            "core:iron_ore" => 1,
+            "core:Iron-Ore" => 2,
+            "core:iron_ingot" => 3,
+            "core:gold_ore" => 4,
+            "core:iron_block_ore" => 6,
+            "deco:ironwood_door" => 5,
+        }
+    }
+
+    #[cfg(feature = "fuzzy")]
+    #[test]
+    fn fuzzy_ranking() {
+        let tab = fuzzy_fixture();
+        let ids: Vec<_> = tab
+            .fuzzy_search("irn ore", 10)
+            .into_iter()
+            .map(|(k, _)| k.as_str())
+            .collect();
+        assert_eq!(
+            ids,
+            ["core:iron_ore", "core:Iron-Ore", "core:iron_block_ore"]
+        );
+        assert_eq!(
+            tab.fuzzy_search("irn ore", 1)[0].0.as_str(),
+            "core:iron_ore"
+        );
+    }
+
+    #[cfg(feature = "fuzzy")]
+    #[test]
+    fn fuzzy_non_match() {
+        let tab = fuzzy_fixture();
+        assert!(tab.fuzzy_search("copper", 10).is_empty());
+        assert!(tab.fuzzy_search("ingot ore", 10).is_empty());
+    }
+
+    #[cfg(feature = "fuzzy")]
+    #[test]
+    fn fuzzy_empty_query() {
+        let tab = fuzzy_fixture();
+        let res = tab.fuzzy_search("", 2);
+        assert_eq!(res.len(), 2);
+        assert!(res.iter().all(|(_, s)| *s == 0));
+        assert_eq!(res[0].0.as_str(), "core:Iron-Ore");
+    }
+
+    #[test]
+    fn merge_with_sums() {
+        let mut a = regtab! { u32; "core:iron" => 3, "core:gold" => 1 };
+        let b = regtab! { u32; "core:iron" => 4, "core:tin" => 2 };
+        a.merge_with(b, |_, x, y| x + y);
+        assert_eq!(*a.get("core:iron").unwrap(), 7);
+        assert_eq!(*a.get("core:gold").unwrap(), 1);
+        assert_eq!(*a.get("core:tin").unwrap(), 2);
+        assert_eq!(a.len(), 3);
+        let mut a = regtab! { &str; "core:iron" => "existing" };
+        let b = regtab! { &str; "core:iron" => "incoming" };
+        a.merge_with(b, |k, x, y| {
+            assert_eq!(k.as_str(), "core:iron");
+            assert_eq!((x, y), ("existing", "incoming"));
+            y
+        });
+        assert_eq!(*a.get("core:iron").unwrap(), "incoming");
+    }
+
+    #[test]
+    fn update_where_subset() {
+        let tab = regtab! { u32; "core:a" => 1, "core:b" => 2, "deco:c" => 3, "deco:d" => 4 };
+        tab.update_where(|k, v| k.modname() == "deco" || *v == 1, |v| *v *= 10);
+        assert_eq!(*tab.get("core:a").unwrap(), 10);
+        assert_eq!(*tab.get("core:b").unwrap(), 2);
+        assert_eq!(*tab.get("deco:c").unwrap(), 30);
+        assert_eq!(*tab.get("deco:d").unwrap(), 40);
+    }
+
+    fn completion_fixture() -> RegTab<()> {
+        regtab! { ();
+            "core:iron" => (),
+            "core:iron_ore" => (),
+            "core:gold" => (),
+            "cooking:pan" => (),
+            "deco:lamp" => (),
+        }
+    }
+
+    #[test]
+    fn complete_modules_phase() {
+        let tab = completion_fixture();
+        assert_eq!(tab.complete_modules("co", 10), ["cooking", "core"]);
+        assert_eq!(tab.complete_modules("co", 1), ["cooking"]);
+        assert_eq!(tab.complete_modules("", 10), ["cooking", "core", "deco"]);
+        assert!(tab.complete_modules("x", 10).is_empty());
+    }
+
+    #[test]
+    fn complete_names_phase() {
+        let tab = completion_fixture();
+        let ids = |p, n| -> Vec<_> { tab.complete(p, n).iter().map(|i| i.as_str()).collect() };
+        assert_eq!(
+            ids("core:", 10),
+            ["core:gold", "core:iron", "core:iron_ore"]
+        );
+        assert_eq!(ids("core:i", 10), ["core:iron", "core:iron_ore"]);
+        assert_eq!(ids("core:", 2), ["core:gold", "core:iron"]);
+        assert!(ids("core:x", 10).is_empty());
+        assert!(ids("core:", 0).is_empty());
+    }
+
+    #[test]
+    fn contains_all_and_missing() {
+        let tab = regtab! { u32; "core:a" => 1, "core:b" => 2 };
+        let ids = [
+            Id::new("core:z"),
+            Id::new("core:a"),
+            Id::new("core:y"),
+            Id::new("core:b"),
+        ];
+        assert!(!tab.contains_all(&ids));
+        assert!(tab.contains_all(&ids[1..2]));
+        assert!(tab.contains_all(&[]));
+        let missing: Vec<_> = tab.missing(&ids).iter().map(|i| i.as_str()).collect();
+        assert_eq!(missing, ["core:z", "core:y"]);
+        assert!(tab.missing(&ids[1..2]).is_empty());
+    }
+
+    #[test]
+    fn count_by_key() {
+        let tab = regtab! { u32;
+            "core:a" => 1,
+            "core:b" => 12,
+            "core:c" => 15,
+            "core:d" => 7,
+            "core:e" => 30,
+        };
+        let counts = tab.count_by(|v| v / 10);
+        assert_eq!(counts.len(), 3);
+        assert_eq!(counts[&0], 2);
+        assert_eq!(counts[&1], 2);
+        assert_eq!(counts[&3], 1);
+        assert!(!counts.contains_key(&2));
+        assert!(regtab! { u32; }.count_by(|v| *v).is_empty());
+    }
+
+    #[test]
+    fn to_vec_deterministic() {
+        let a = regtab! { u32; "core:b" => 2, "deco:a" => 3, "core:a" => 1 };
+        let b = regtab! { u32; "deco:a" => 3, "core:a" => 1, "core:b" => 2 };
+        let v = a.to_vec();
+        assert_eq!(v, b.to_vec());
+        assert_eq!(
+            v,
+            [
+                ("core:a".to_string(), 1),
+                ("core:b".to_string(), 2),
+                ("deco:a".to_string(), 3)
+            ]
+        );
+    }
+
+    #[derive(Debug, PartialEq)]
+    struct Gem(u32);
+
+    static GEMS: std::sync::OnceLock<&'static RegTab<Gem>> = std::sync::OnceLock::new();
+
+    crate::has_regtab!(
+        Gem,
+        **GEMS.get_or_init(|| regtab! { Gem; "core:ruby" => Gem(1) }.into_static())
+    );
+
+    #[test]
+    fn into_static_as_reg_tab() {
+        assert_eq!(*Gem::reg_tab().get("core:ruby").unwrap(), Gem(1));
+        Gem::reg_tab().insert("core:opal", Gem(2));
+        assert!(std::ptr::eq(Gem::reg_tab(), Gem::reg_tab()));
+        let rp = crate::Rp::<Gem>::lookup("core:opal").unwrap();
+        assert_eq!(*rp, Gem(2));
+        drop(rp);
+        assert!(Gem::reg_tab().remove("core:opal").is_some());
+        assert_eq!(Gem::reg_tab().len(), 1);
+    }
+
+    #[test]
+    fn dedup_shares_values() {
+        let tab = regtab! { String;
+            "core:a" => "stone".into(),
+            "core:b" => "stone".into(),
+            "core:c" => "dirt".into(),
+            "core:d" => "stone".into(),
+        };
+        let (tab, dups) = tab.dedup();
+        assert_eq!(dups, 2);
+        assert_eq!(tab.len(), 4);
+        let a = tab.get("core:a").unwrap().clone();
+        assert!(Arc::ptr_eq(&a, &tab.get("core:b").unwrap()));
+        assert!(Arc::ptr_eq(&a, &tab.get("core:d").unwrap()));
+        assert!(!Arc::ptr_eq(&a, &tab.get("core:c").unwrap()));
+        // Three entries and the clone above share one allocation.
+        assert_eq!(Arc::strong_count(&a), 4);
+    }
+
+    #[test]
+    fn backed_tab_loads_once() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+        let calls = Arc::new(AtomicUsize::new(0));
+        let c = calls.clone();
+        let tab = BackedTab::new(move |id: &Id<u32>| {
+            c.fetch_add(1, Ordering::Relaxed);
+            (id.modname() == "core").then(|| id.idname().len() as u32)
+        });
+        assert_eq!(*tab.get(Id::new("core:iron")).unwrap(), 4);
+        assert_eq!(calls.load(Ordering::Relaxed), 1);
+        assert!(tab.cached().contains_key("core:iron"));
+        assert_eq!(*tab.get(Id::new("core:iron")).unwrap(), 4);
+        assert_eq!(calls.load(Ordering::Relaxed), 1);
+        assert!(tab.get(Id::new("deco:lamp")).is_none());
+        assert_eq!(calls.load(Ordering::Relaxed), 2);
+        assert!(!tab.cached().contains_key("deco:lamp"));
+        assert_eq!(tab.cached().len(), 1);
+    }
+
+    #[test]
+    fn from_parts_pairs() {
+        let ids = vec![Id::new("core:a"), Id::new("core:b")];
+        let tab = RegTab::from_parts(ids, vec![1, 2]).unwrap();
+        assert!(tab == regtab! { u32; "core:a" => 1, "core:b" => 2 });
+    }
+
+    #[test]
+    fn from_parts_length_mismatch() {
+        let res = RegTab::from_parts(vec![Id::new("core:a"), Id::new("core:b")], vec![1]);
+        assert_eq!(
+            res.err(),
+            Some(FromPartsError::LengthMismatch { ids: 2, values: 1 })
+        );
+    }
+
+    #[test]
+    fn from_parts_duplicates() {
+        let ids = vec![Id::new("core:a"), Id::new("core:b"), Id::new("core:a")];
+        let res = RegTab::from_parts(ids, vec![1, 2, 3]);
+        assert_eq!(res.err(), Some(FromPartsError::DuplicateId("core:a")));
+    }
+}